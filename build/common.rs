@@ -15,6 +15,9 @@ use glob::{MatchOptions, Pattern};
 thread_local! {
     /// The errors encountered by the build script while executing commands.
     static COMMAND_ERRORS: RefCell<HashMap<String, Vec<String>>> = RefCell::default();
+
+    /// The directories searched by the build script for `libclang` libraries.
+    static SEARCHED_DIRECTORIES: RefCell<Vec<PathBuf>> = RefCell::default();
 }
 
 /// Adds an error encountered by the build script while executing a command.
@@ -203,9 +206,17 @@ const DIRECTORIES_ILLUMOS: &[&str] = &[
 // Searching
 //================================================
 
+/// Returns the directories searched for `libclang` libraries since this
+/// function was last called.
+pub fn take_searched_directories() -> Vec<PathBuf> {
+    SEARCHED_DIRECTORIES.with(|d| d.take())
+}
+
 /// Finds the files in a directory that match one or more filename glob patterns
 /// and returns the paths to and filenames of those files.
 fn search_directory(directory: &Path, filenames: &[String]) -> Vec<(PathBuf, String)> {
+    SEARCHED_DIRECTORIES.with(|d| d.borrow_mut().push(directory.into()));
+
     // Escape the specified directory in case it contains characters that have
     // special meaning in glob patterns (e.g., `[` or `]`).
     let directory = Pattern::escape(directory.to_str().unwrap());
@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::env;
+use std::fmt;
 use std::fs::File;
 use std::io::{self, Error, ErrorKind, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
@@ -56,29 +57,51 @@ fn parse_pe_header(path: &Path) -> io::Result<(u16, u16)> {
     return Ok((magic_number, machine_type));
 }
 
+/// The reason a shared library does not match the target platform.
+#[derive(Debug)]
+pub enum InvalidLibrary {
+    /// The shared library could not be read or has an invalid header.
+    Header(io::Error),
+    /// The shared library was built for a different architecture.
+    Architecture(String),
+}
+
+impl fmt::Display for InvalidLibrary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidLibrary::Header(error) => write!(f, "{}", error),
+            InvalidLibrary::Architecture(message) => write!(f, "{}", message),
+        }
+    }
+}
+
 /// Checks that a `libclang` shared library matches the target platform.
-fn validate_library(path: &Path) -> Result<(), String> {
+pub fn validate_library(path: &Path) -> Result<(), InvalidLibrary> {
     if target_os!("linux") || target_os!("freebsd") {
-        let class = parse_elf_header(path).map_err(|e| e.to_string())?;
+        let class = parse_elf_header(path).map_err(InvalidLibrary::Header)?;
 
         if target_pointer_width!("32") && class != 1 {
-            return Err("invalid ELF class (64-bit)".into());
+            return Err(InvalidLibrary::Architecture(
+                "invalid ELF class (64-bit)".into(),
+            ));
         }
 
         if target_pointer_width!("64") && class != 2 {
-            return Err("invalid ELF class (32-bit)".into());
+            return Err(InvalidLibrary::Architecture(
+                "invalid ELF class (32-bit)".into(),
+            ));
         }
 
         Ok(())
     } else if target_os!("windows") {
-        let (magic, machine_type) = parse_pe_header(path).map_err(|e| e.to_string())?;
+        let (magic, machine_type) = parse_pe_header(path).map_err(InvalidLibrary::Header)?;
 
         if target_pointer_width!("32") && magic != 267 {
-            return Err("invalid DLL (64-bit)".into());
+            return Err(InvalidLibrary::Architecture("invalid DLL (64-bit)".into()));
         }
 
         if target_pointer_width!("64") && magic != 523 {
-            return Err("invalid DLL (32-bit)".into());
+            return Err(InvalidLibrary::Architecture("invalid DLL (32-bit)".into()));
         }
 
         let arch_mismatch = match machine_type {
@@ -89,7 +112,9 @@ fn validate_library(path: &Path) -> Result<(), String> {
         };
 
         if let Some(arch) = arch_mismatch {
-            Err(format!("invalid DLL ({arch})"))
+            Err(InvalidLibrary::Architecture(format!(
+                "invalid DLL ({arch})"
+            )))
         } else {
             Ok(())
         }
@@ -115,9 +140,24 @@ fn parse_version(filename: &str) -> Vec<u32> {
     version.split('.').map(|s| s.parse().unwrap_or(0)).collect()
 }
 
-/// Finds `libclang` shared libraries and returns the paths to, filenames of,
-/// and versions of those shared libraries.
-fn search_libclang_directories(runtime: bool) -> Result<Vec<(PathBuf, String, Vec<u32>)>, String> {
+/// The `libclang` shared libraries found by searching for `libclang` shared
+/// libraries.
+#[derive(Debug, Default)]
+pub struct Search {
+    /// The filename glob patterns that were searched for.
+    pub patterns: Vec<String>,
+    /// The directories that were searched.
+    pub directories: Vec<PathBuf>,
+    /// The paths to, filenames of, and versions of the `libclang` shared
+    /// libraries that match the target platform.
+    pub valid: Vec<(PathBuf, String, Vec<u32>)>,
+    /// The paths to the `libclang` shared libraries that do not match the
+    /// target platform and the reasons why.
+    pub invalid: Vec<(PathBuf, InvalidLibrary)>,
+}
+
+/// Finds `libclang` shared libraries and validates them.
+pub fn search(runtime: bool) -> Search {
     let mut files = vec![format!(
         "{}clang{}",
         env::consts::DLL_PREFIX,
@@ -157,43 +197,62 @@ fn search_libclang_directories(runtime: bool) -> Result<Vec<(PathBuf, String, Ve
     }
 
     // Find and validate `libclang` shared libraries and collect the versions.
-    let mut valid = vec![];
-    let mut invalid = vec![];
+    let mut search = Search::default();
+    common::take_searched_directories();
     for (directory, filename) in common::search_libclang_directories(&files, "LIBCLANG_PATH") {
         let path = directory.join(&filename);
         match validate_library(&path) {
             Ok(()) => {
                 let version = parse_version(&filename);
-                valid.push((directory, filename, version))
+                search.valid.push((directory, filename, version))
             }
-            Err(message) => invalid.push(format!("({}: {})", path.display(), message)),
+            Err(invalid) => search.invalid.push((path, invalid)),
         }
     }
 
-    if !valid.is_empty() {
-        return Ok(valid);
+    search.patterns = files;
+    search.directories = common::take_searched_directories();
+    search
+}
+
+/// Finds `libclang` shared libraries and returns the paths to, filenames of,
+/// and versions of those shared libraries.
+pub fn search_libclang_directories(
+    runtime: bool,
+) -> Result<Vec<(PathBuf, String, Vec<u32>)>, String> {
+    let search = search(runtime);
+
+    if !search.valid.is_empty() {
+        return Ok(search.valid);
     }
 
     let message = format!(
         "couldn't find any valid shared libraries matching: [{}], set the \
          `LIBCLANG_PATH` environment variable to a path where one of these files \
          can be found (invalid: [{}])",
-        files
+        search
+            .patterns
             .iter()
             .map(|f| format!("'{}'", f))
             .collect::<Vec<_>>()
             .join(", "),
-        invalid.join(", "),
+        search
+            .invalid
+            .iter()
+            .map(|(p, i)| format!("({}: {})", p.display(), i))
+            .collect::<Vec<_>>()
+            .join(", "),
     );
 
     Err(message)
 }
 
-/// Finds the "best" `libclang` shared library and returns the directory and
-/// filename of that library.
-pub fn find(runtime: bool) -> Result<(PathBuf, String), String> {
-    search_libclang_directories(runtime)?
+/// Returns the index of the "best" `libclang` shared library in the supplied
+/// list of `libclang` shared libraries.
+pub fn select(libraries: &[(PathBuf, String, Vec<u32>)]) -> Option<usize> {
+    libraries
         .iter()
+        .enumerate()
         // We want to find the `libclang` shared library with the highest
         // version number, hence `max_by_key` below.
         //
@@ -208,10 +267,17 @@ pub fn find(runtime: bool) -> Result<(PathBuf, String), String> {
         // the tiebreaking behavior we want. This is easily fixed by reversing
         // the list first.
         .rev()
-        .max_by_key(|f| &f.2)
-        .cloned()
-        .map(|(path, filename, _)| (path, filename))
-        .ok_or_else(|| "unreachable".into())
+        .max_by_key(|(_, f)| &f.2)
+        .map(|(i, _)| i)
+}
+
+/// Finds the "best" `libclang` shared library and returns the directory and
+/// filename of that library.
+pub fn find(runtime: bool) -> Result<(PathBuf, String), String> {
+    let libraries = search_libclang_directories(runtime)?;
+    let index = select(&libraries).ok_or_else(|| "unreachable".to_string())?;
+    let (directory, filename, _) = libraries[index].clone();
+    Ok((directory, filename))
 }
 
 //================================================
@@ -0,0 +1,333 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Provides owned wrappers for `libclang` compilation databases.
+
+use std::error::Error;
+use std::ffi::CString;
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::ptr;
+
+#[cfg(feature = "clang_3_8")]
+use super::index::{self, Index, TranslationUnit, TranslationUnitError, Unsaved};
+use super::string::ClangString;
+use super::*;
+
+//================================================
+// Enums
+//================================================
+
+/// An error encountered while loading a compilation database.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CompilationDatabaseError {
+    /// The compilation database could not be loaded (e.g., the directory does
+    /// not contain a `compile_commands.json` file).
+    CanNotLoadDatabase,
+}
+
+impl CompilationDatabaseError {
+    /// Converts a `libclang` error code into an error
+    /// (`CXCompilationDatabase_NoError` is converted into `Ok(())`).
+    pub fn from_raw(code: CXCompilationDatabase_Error) -> Result<(), CompilationDatabaseError> {
+        match code {
+            CXCompilationDatabase_NoError => Ok(()),
+            _ => Err(CompilationDatabaseError::CanNotLoadDatabase),
+        }
+    }
+
+    /// Returns the `libclang` error code for this error.
+    pub fn to_raw(self) -> CXCompilationDatabase_Error {
+        match self {
+            CompilationDatabaseError::CanNotLoadDatabase => {
+                CXCompilationDatabase_CanNotLoadDatabase
+            }
+        }
+    }
+}
+
+impl fmt::Display for CompilationDatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompilationDatabaseError::CanNotLoadDatabase => {
+                write!(f, "the compilation database could not be loaded")
+            }
+        }
+    }
+}
+
+impl Error for CompilationDatabaseError {}
+
+//================================================
+// Structs
+//================================================
+
+/// An owned `libclang` compilation database which is disposed of when dropped.
+#[derive(Debug)]
+pub struct CompilationDatabase {
+    raw: CXCompilationDatabase,
+}
+
+impl CompilationDatabase {
+    /// Loads the compilation database (i.e., the `compile_commands.json` file)
+    /// in the supplied directory with `clang_CompilationDatabase_fromDirectory`.
+    ///
+    /// Paths which are not valid UTF-8 or which contain null characters are
+    /// reported as `CompilationDatabaseError::CanNotLoadDatabase`.
+    pub fn from_directory(
+        directory: impl AsRef<Path>,
+    ) -> Result<CompilationDatabase, CompilationDatabaseError> {
+        let directory = directory
+            .as_ref()
+            .to_str()
+            .and_then(|d| CString::new(d).ok());
+        let directory = directory.ok_or(CompilationDatabaseError::CanNotLoadDatabase)?;
+
+        unsafe {
+            let mut error = CXCompilationDatabase_NoError;
+            let raw = clang_CompilationDatabase_fromDirectory(directory.as_ptr(), &mut error);
+            if raw.is_null() {
+                return Err(CompilationDatabaseError::CanNotLoadDatabase);
+            }
+
+            let database = CompilationDatabase::from_raw(raw);
+            CompilationDatabaseError::from_raw(error)?;
+            Ok(database)
+        }
+    }
+
+    /// Constructs a new `CompilationDatabase` which takes ownership of the
+    /// supplied `libclang` compilation database.
+    ///
+    /// # Safety
+    ///
+    /// The supplied compilation database must not be null and must not be
+    /// disposed of elsewhere.
+    pub unsafe fn from_raw(raw: CXCompilationDatabase) -> CompilationDatabase {
+        CompilationDatabase { raw }
+    }
+
+    /// Returns the underlying `libclang` compilation database.
+    pub fn as_raw(&self) -> CXCompilationDatabase {
+        self.raw
+    }
+
+    /// Returns the compile commands for the supplied file in this compilation
+    /// database (empty if `file` contains null characters).
+    pub fn compile_commands(&self, file: &str) -> CompileCommands<'_> {
+        match CString::new(file) {
+            Ok(file) => unsafe {
+                CompileCommands::from_raw(clang_CompilationDatabase_getCompileCommands(
+                    self.raw,
+                    file.as_ptr(),
+                ))
+            },
+            Err(_) => unsafe { CompileCommands::from_raw(ptr::null_mut()) },
+        }
+    }
+
+    /// Returns all of the compile commands in this compilation database.
+    pub fn all_compile_commands(&self) -> CompileCommands<'_> {
+        unsafe {
+            CompileCommands::from_raw(clang_CompilationDatabase_getAllCompileCommands(self.raw))
+        }
+    }
+}
+
+impl Drop for CompilationDatabase {
+    fn drop(&mut self) {
+        unsafe { clang_CompilationDatabase_dispose(self.raw) };
+    }
+}
+
+/// An owned collection of `libclang` compile commands which is disposed of
+/// when dropped.
+#[derive(Debug)]
+pub struct CompileCommands<'d> {
+    raw: CXCompileCommands,
+    _database: PhantomData<&'d CompilationDatabase>,
+}
+
+impl<'d> CompileCommands<'d> {
+    /// Constructs a new `CompileCommands` which takes ownership of the supplied
+    /// `libclang` compile commands.
+    ///
+    /// # Safety
+    ///
+    /// The supplied compile commands must be null (i.e., empty) or must have
+    /// been returned for a compilation database which outlives `'d`, and must
+    /// not be disposed of elsewhere.
+    pub unsafe fn from_raw(raw: CXCompileCommands) -> CompileCommands<'d> {
+        CompileCommands {
+            raw,
+            _database: PhantomData,
+        }
+    }
+
+    /// Returns the underlying `libclang` compile commands.
+    pub fn as_raw(&self) -> CXCompileCommands {
+        self.raw
+    }
+
+    /// Returns the number of compile commands in this collection.
+    pub fn len(&self) -> usize {
+        if self.raw.is_null() {
+            0
+        } else {
+            unsafe { clang_CompileCommands_getSize(self.raw) as usize }
+        }
+    }
+
+    /// Returns whether this collection contains no compile commands.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the compile command at the supplied index in this collection, if
+    /// any.
+    pub fn get(&self, index: usize) -> Option<CompileCommand<'_>> {
+        if index < self.len() {
+            let raw = unsafe { clang_CompileCommands_getCommand(self.raw, index as c_uint) };
+            Some(CompileCommand {
+                raw,
+                _commands: PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over the compile commands in this collection.
+    pub fn iter(&self) -> impl Iterator<Item = CompileCommand<'_>> {
+        (0..self.len()).filter_map(move |i| self.get(i))
+    }
+}
+
+impl<'d> Drop for CompileCommands<'d> {
+    fn drop(&mut self) {
+        if !self.raw.is_null() {
+            unsafe { clang_CompileCommands_dispose(self.raw) };
+        }
+    }
+}
+
+/// A `libclang` compile command which borrows from a collection of compile
+/// commands.
+#[derive(Copy, Clone, Debug)]
+pub struct CompileCommand<'c> {
+    raw: CXCompileCommand,
+    _commands: PhantomData<&'c ()>,
+}
+
+impl<'c> CompileCommand<'c> {
+    /// Returns the underlying `libclang` compile command.
+    pub fn as_raw(&self) -> CXCompileCommand {
+        self.raw
+    }
+
+    /// Returns the working directory this compile command was executed in.
+    pub fn directory(&self) -> String {
+        unsafe { ClangString::from_raw(clang_CompileCommand_getDirectory(self.raw)).into() }
+    }
+
+    /// Returns the name of the source file compiled by this compile command.
+    ///
+    /// Only available on `libclang` 3.8 and later.
+    #[cfg(feature = "clang_3_8")]
+    pub fn filename(&self) -> String {
+        unsafe { ClangString::from_raw(clang_CompileCommand_getFilename(self.raw)).into() }
+    }
+
+    /// Returns the number of arguments in this compile command (including the
+    /// name of the compiler executable).
+    pub fn num_arguments(&self) -> usize {
+        unsafe { clang_CompileCommand_getNumArgs(self.raw) as usize }
+    }
+
+    /// Returns the argument at the supplied index in this compile command, if
+    /// any.
+    pub fn argument(&self, index: usize) -> Option<String> {
+        if index < self.num_arguments() {
+            Some(unsafe {
+                ClangString::from_raw(clang_CompileCommand_getArg(self.raw, index as c_uint)).into()
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over the arguments in this compile command
+    /// (including the name of the compiler executable).
+    pub fn arguments(&self) -> impl Iterator<Item = String> + 'c {
+        let command = *self;
+        (0..self.num_arguments()).filter_map(move |i| command.argument(i))
+    }
+
+    /// Returns the files mapped to other contents in this compile command.
+    ///
+    /// Only available on `libclang` 3.8 and later.
+    #[cfg(feature = "clang_3_8")]
+    pub fn mapped_sources(&self) -> Vec<Unsaved> {
+        unsafe {
+            (0..clang_CompileCommand_getNumMappedSources(self.raw))
+                .map(|i| {
+                    let path = ClangString::from_raw(clang_CompileCommand_getMappedSourcePath(
+                        self.raw, i,
+                    ));
+                    let contents = ClangString::from_raw(
+                        clang_CompileCommand_getMappedSourceContent(self.raw, i),
+                    );
+                    Unsaved::new(&String::from(path), String::from(contents))
+                })
+                .collect()
+        }
+    }
+
+    /// Parses the source file compiled by this compile command (and the
+    /// supplied unsaved files) into a translation unit with
+    /// `clang_parseTranslationUnit2FullArgv`.
+    ///
+    /// The arguments in this compile command are passed as is (including the
+    /// name of the compiler executable) with `-working-directory` and the
+    /// directory of this compile command inserted after the name of the
+    /// compiler executable so that relative paths are resolved correctly (and
+    /// so that they are not treated as input files if the arguments contain
+    /// `--`).
+    ///
+    /// Only available on `libclang` 3.8 and later.
+    #[cfg(feature = "clang_3_8")]
+    pub fn parse<'i>(
+        &self,
+        index: &'i Index,
+        unsaved: &[Unsaved],
+        flags: CXTranslationUnit_Flags,
+    ) -> Result<TranslationUnit<'i>, TranslationUnitError> {
+        let mut arguments = self.arguments().collect::<Vec<_>>();
+        let position = arguments.len().min(1);
+        let directory = vec!["-working-directory".into(), self.directory()];
+        arguments.splice(position..position, directory);
+
+        let arguments = arguments
+            .iter()
+            .map(|a| index::to_c_string(a))
+            .collect::<Result<Vec<_>, _>>()?;
+        let arguments = arguments.iter().map(|a| a.as_ptr()).collect::<Vec<_>>();
+        let mut unsaved = unsaved.iter().map(|u| u.as_raw()).collect::<Vec<_>>();
+
+        unsafe {
+            let mut raw = ptr::null_mut();
+            let code = clang_parseTranslationUnit2FullArgv(
+                index.as_raw(),
+                ptr::null(),
+                arguments.as_ptr(),
+                arguments.len() as c_int,
+                unsaved.as_mut_ptr(),
+                unsaved.len() as c_uint,
+                flags,
+                &mut raw,
+            );
+            TranslationUnitError::from_raw(code)?;
+            Ok(TranslationUnit::from_raw(raw))
+        }
+    }
+}
@@ -0,0 +1,378 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Provides owned wrappers for `libclang` code completion results.
+
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::slice;
+
+use super::diagnostic::{Diagnostic, FixIt};
+use super::flags;
+use super::index::{TranslationUnit, Unsaved};
+use super::string::ClangString;
+use super::*;
+
+//================================================
+// Enums
+//================================================
+
+/// A chunk in a completion string.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CompletionChunk {
+    /// A chunk of text (e.g., `CXCompletionChunk_TypedText`).
+    Text {
+        /// The kind of the chunk.
+        kind: CXCompletionChunkKind,
+        /// The text of the chunk.
+        text: String,
+    },
+    /// An optional chunk (`CXCompletionChunk_Optional`) containing a nested
+    /// completion string.
+    Optional(CompletionString),
+}
+
+impl CompletionChunk {
+    /// Returns the kind of this chunk.
+    pub fn kind(&self) -> CXCompletionChunkKind {
+        match self {
+            CompletionChunk::Text { kind, .. } => *kind,
+            CompletionChunk::Optional(_) => CXCompletionChunk_Optional,
+        }
+    }
+}
+
+//================================================
+// Structs
+//================================================
+
+/// The information in a `libclang` completion string.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CompletionString {
+    /// The chunks in the completion string.
+    pub chunks: Vec<CompletionChunk>,
+    /// The priority of the completion string (lower values are more likely to
+    /// be appropriate).
+    pub priority: u32,
+    /// The availability of the completion string (e.g.,
+    /// `CXAvailability_Available`).
+    pub availability: CXAvailabilityKind,
+    /// The annotations of the completion string.
+    pub annotations: Vec<String>,
+    /// The brief documentation comment for the completion string, if any.
+    pub brief_comment: Option<String>,
+}
+
+impl CompletionString {
+    /// Constructs a new `CompletionString` (and its nested completion strings)
+    /// from the supplied `libclang` completion string.
+    ///
+    /// # Safety
+    ///
+    /// The supplied completion string must be a valid `libclang` completion
+    /// string.
+    pub unsafe fn from_raw(raw: CXCompletionString) -> CompletionString {
+        let chunks = (0..clang_getNumCompletionChunks(raw))
+            .map(|i| match clang_getCompletionChunkKind(raw, i) {
+                CXCompletionChunk_Optional => {
+                    let string = clang_getCompletionChunkCompletionString(raw, i);
+                    CompletionChunk::Optional(CompletionString::from_raw(string))
+                }
+                kind => {
+                    let text = ClangString::from_raw(clang_getCompletionChunkText(raw, i)).into();
+                    CompletionChunk::Text { kind, text }
+                }
+            })
+            .collect();
+
+        let annotations = (0..clang_getCompletionNumAnnotations(raw))
+            .map(|i| ClangString::from_raw(clang_getCompletionAnnotation(raw, i)).into())
+            .collect();
+
+        let brief_comment =
+            String::from(ClangString::from_raw(clang_getCompletionBriefComment(raw)));
+        let brief_comment = if brief_comment.is_empty() {
+            None
+        } else {
+            Some(brief_comment)
+        };
+
+        CompletionString {
+            chunks,
+            priority: clang_getCompletionPriority(raw),
+            availability: clang_getCompletionAvailability(raw),
+            annotations,
+            brief_comment,
+        }
+    }
+
+    /// Returns the text of the `CXCompletionChunk_TypedText` chunk in this
+    /// completion string, if any.
+    pub fn typed_text(&self) -> Option<&str> {
+        self.chunks.iter().find_map(|c| match c {
+            CompletionChunk::Text {
+                kind: CXCompletionChunk_TypedText,
+                text,
+            } => Some(&text[..]),
+            _ => None,
+        })
+    }
+
+    /// Returns the text that should be used to filter this completion string
+    /// against what the user has typed (i.e., the typed text).
+    pub fn filter_text(&self) -> &str {
+        self.typed_text().unwrap_or("")
+    }
+
+    /// Renders this completion string as a plain label (e.g.,
+    /// `add(int a, int b)`).
+    ///
+    /// The label contains the text of every chunk (including the chunks in
+    /// optional chunks) except for the result type.
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        render_label(self, &mut label);
+        label
+    }
+
+    /// Renders this completion string as an LSP (or TextMate) snippet (e.g.,
+    /// `add(${1:int a}, ${2:int b})`).
+    ///
+    /// Placeholder and current parameter chunks are rendered as numbered
+    /// placeholders while result type, informative, and optional chunks are
+    /// omitted. Any `$`, `}`, and `\` characters in the text are escaped.
+    pub fn snippet(&self) -> String {
+        let mut snippet = String::new();
+        let mut placeholders = 0;
+        for chunk in &self.chunks {
+            match chunk {
+                CompletionChunk::Text {
+                    kind: CXCompletionChunk_ResultType,
+                    ..
+                }
+                | CompletionChunk::Text {
+                    kind: CXCompletionChunk_Informative,
+                    ..
+                }
+                | CompletionChunk::Optional(_) => {}
+                CompletionChunk::Text {
+                    kind: CXCompletionChunk_Placeholder,
+                    text,
+                }
+                | CompletionChunk::Text {
+                    kind: CXCompletionChunk_CurrentParameter,
+                    text,
+                } => {
+                    placeholders += 1;
+                    snippet.push_str(&format!("${{{}:", placeholders));
+                    escape(text, &mut snippet);
+                    snippet.push('}');
+                }
+                CompletionChunk::Text { text, .. } => escape(text, &mut snippet),
+            }
+        }
+        snippet
+    }
+}
+
+/// The information in a `libclang` code completion result.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CompletionResult {
+    /// The kind of the entity the result refers to (e.g., `CXCursor_FieldDecl`).
+    pub cursor_kind: CXCursorKind,
+    /// The completion string for the result.
+    pub string: CompletionString,
+    /// The fix-its which must be applied before the result is inserted (always
+    /// empty before `libclang` 7.0).
+    pub fix_its: Vec<FixIt>,
+}
+
+/// An owned set of `libclang` code completion results which is disposed of
+/// when dropped.
+#[derive(Debug)]
+pub struct CodeCompletion<'t> {
+    raw: *mut CXCodeCompleteResults,
+    _tu: PhantomData<&'t ()>,
+}
+
+impl<'t> CodeCompletion<'t> {
+    /// Performs code completion at the supplied location in the supplied
+    /// translation unit with `clang_codeCompleteAt`.
+    ///
+    /// Returns `None` if code completion fails or if `file` contains null
+    /// characters.
+    pub fn complete<'i>(
+        tu: &'t TranslationUnit<'i>,
+        file: &str,
+        line: u32,
+        column: u32,
+        unsaved: &[Unsaved],
+        flags: CXCodeComplete_Flags,
+    ) -> Option<CodeCompletion<'t>> {
+        let file = CString::new(file).ok()?;
+        let mut unsaved = unsaved.iter().map(|u| u.as_raw()).collect::<Vec<_>>();
+
+        let raw = unsafe {
+            clang_codeCompleteAt(
+                tu.as_raw(),
+                file.as_ptr(),
+                line as c_uint,
+                column as c_uint,
+                unsaved.as_mut_ptr(),
+                unsaved.len() as c_uint,
+                flags,
+            )
+        };
+
+        if raw.is_null() {
+            None
+        } else {
+            Some(unsafe { CodeCompletion::from_raw(raw) })
+        }
+    }
+
+    /// Constructs a new `CodeCompletion` which takes ownership of the supplied
+    /// `libclang` code completion results.
+    ///
+    /// # Safety
+    ///
+    /// The supplied results must not be null, must have been returned by
+    /// `clang_codeCompleteAt` for a translation unit which outlives `'t`, and
+    /// must not be disposed of elsewhere.
+    pub unsafe fn from_raw(raw: *mut CXCodeCompleteResults) -> CodeCompletion<'t> {
+        CodeCompletion {
+            raw,
+            _tu: PhantomData,
+        }
+    }
+
+    /// Returns the underlying `libclang` code completion results.
+    pub fn as_raw(&self) -> *mut CXCodeCompleteResults {
+        self.raw
+    }
+
+    /// Returns the underlying `libclang` code completion results as a slice.
+    fn as_slice(&self) -> &[CXCompletionResult] {
+        let results = unsafe { &*self.raw };
+        if results.Results.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(results.Results, results.NumResults as usize) }
+        }
+    }
+
+    /// Returns the number of results in this set.
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// Returns whether this set contains no results.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the result at the supplied index in this set, if any.
+    pub fn get(&self, index: usize) -> Option<CompletionResult> {
+        let result = self.as_slice().get(index)?;
+        let string = unsafe { CompletionString::from_raw(result.CompletionString) };
+        Some(CompletionResult {
+            cursor_kind: result.CursorKind,
+            string,
+            fix_its: self.fix_its(index),
+        })
+    }
+
+    /// Returns an iterator over the results in this set.
+    pub fn iter(&self) -> impl Iterator<Item = CompletionResult> + '_ {
+        (0..self.len()).filter_map(move |i| self.get(i))
+    }
+
+    /// Sorts the results in this set alphabetically with
+    /// `clang_sortCodeCompletionResults`.
+    pub fn sort(&mut self) {
+        let results = unsafe { &*self.raw };
+        if !results.Results.is_null() {
+            unsafe { clang_sortCodeCompletionResults(results.Results, results.NumResults) };
+        }
+    }
+
+    /// Returns the kinds of completions that are appropriate at the location
+    /// code completion was performed at.
+    pub fn contexts(&self) -> flags::CXCompletionContext {
+        let contexts = unsafe { clang_codeCompleteGetContexts(self.raw) };
+        flags::CXCompletionContext::from_bits(contexts as CXCompletionContext)
+    }
+
+    /// Returns the diagnostics produced while performing code completion.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        unsafe {
+            (0..clang_codeCompleteGetNumDiagnostics(self.raw))
+                .map(|i| {
+                    let raw = clang_codeCompleteGetDiagnostic(self.raw, i);
+                    let diagnostic = Diagnostic::from_raw(raw);
+                    clang_disposeDiagnostic(raw);
+                    diagnostic
+                })
+                .collect()
+        }
+    }
+
+    #[cfg(feature = "clang_7_0")]
+    fn fix_its(&self, index: usize) -> Vec<FixIt> {
+        use super::source::SourceRange;
+
+        let index = index as c_uint;
+        unsafe {
+            (0..clang_getCompletionNumFixIts(self.raw, index))
+                .map(|i| {
+                    let mut range = CXSourceRange::default();
+                    let replacement = clang_getCompletionFixIt(self.raw, index, i, &mut range);
+                    let replacement = ClangString::from_raw(replacement).into();
+                    FixIt {
+                        range: SourceRange::from_raw(range),
+                        replacement,
+                    }
+                })
+                .collect()
+        }
+    }
+
+    #[cfg(not(feature = "clang_7_0"))]
+    fn fix_its(&self, _: usize) -> Vec<FixIt> {
+        vec![]
+    }
+}
+
+impl<'t> Drop for CodeCompletion<'t> {
+    fn drop(&mut self) {
+        unsafe { clang_disposeCodeCompleteResults(self.raw) };
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+/// Appends the label for the supplied completion string to the supplied label.
+fn render_label(string: &CompletionString, label: &mut String) {
+    for chunk in &string.chunks {
+        match chunk {
+            CompletionChunk::Text {
+                kind: CXCompletionChunk_ResultType,
+                ..
+            } => {}
+            CompletionChunk::Text { text, .. } => label.push_str(text),
+            CompletionChunk::Optional(string) => render_label(string, label),
+        }
+    }
+}
+
+/// Appends the supplied text to the supplied snippet, escaping any characters
+/// that have special meanings in snippets.
+fn escape(text: &str, snippet: &mut String) {
+    for c in text.chars() {
+        if matches!(c, '$' | '}' | '\\') {
+            snippet.push('\\');
+        }
+        snippet.push(c);
+    }
+}
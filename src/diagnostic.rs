@@ -0,0 +1,267 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Provides owned representations of `libclang` diagnostics.
+
+use std::error::Error;
+use std::ffi::CString;
+use std::fmt;
+use std::path::Path;
+
+use super::source::{SourceLocation, SourceRange};
+use super::string::ClangString;
+use super::*;
+
+//================================================
+// Enums
+//================================================
+
+/// An error encountered while loading a serialized diagnostics file.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LoadDiagnosticsError {
+    /// An unknown error occurred.
+    Unknown(String),
+    /// The file could not be loaded (e.g., it does not exist).
+    CannotLoad(String),
+    /// The file is not a valid serialized diagnostics file.
+    InvalidFile(String),
+}
+
+impl LoadDiagnosticsError {
+    /// Returns the `libclang` error code for this error.
+    pub fn to_raw(&self) -> CXLoadDiag_Error {
+        match self {
+            LoadDiagnosticsError::Unknown(_) => CXLoadDiag_Unknown,
+            LoadDiagnosticsError::CannotLoad(_) => CXLoadDiag_CannotLoad,
+            LoadDiagnosticsError::InvalidFile(_) => CXLoadDiag_InvalidFile,
+        }
+    }
+
+    /// Returns the error message for this error.
+    pub fn message(&self) -> &str {
+        match self {
+            LoadDiagnosticsError::Unknown(message) => message,
+            LoadDiagnosticsError::CannotLoad(message) => message,
+            LoadDiagnosticsError::InvalidFile(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for LoadDiagnosticsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadDiagnosticsError::Unknown(message) => {
+                write!(f, "failed to load diagnostics file: {}", message)
+            }
+            LoadDiagnosticsError::CannotLoad(message) => {
+                write!(f, "could not load diagnostics file: {}", message)
+            }
+            LoadDiagnosticsError::InvalidFile(message) => {
+                write!(f, "invalid diagnostics file: {}", message)
+            }
+        }
+    }
+}
+
+impl Error for LoadDiagnosticsError {}
+
+//================================================
+// Structs
+//================================================
+
+/// A suggested change which would resolve a diagnostic.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FixIt {
+    /// The range of source code to be replaced (empty for insertions).
+    pub range: SourceRange,
+    /// The replacement source code (empty for removals).
+    pub replacement: String,
+}
+
+/// The information in a `libclang` diagnostic and its child diagnostics.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    /// The severity of the diagnostic (e.g., `CXDiagnostic_Warning`).
+    pub severity: CXDiagnosticSeverity,
+    /// The location of the diagnostic.
+    pub location: SourceLocation,
+    /// The text of the diagnostic.
+    pub spelling: String,
+    /// The name of the category of the diagnostic (e.g., `Semantic Issue`).
+    pub category: String,
+    /// The command line option that enabled the diagnostic (e.g.,
+    /// `-Wunused-variable`), if any.
+    pub option: Option<String>,
+    /// The command line option that disables the diagnostic (e.g.,
+    /// `-Wno-unused-variable`), if any.
+    pub disable_option: Option<String>,
+    /// The source ranges associated with the diagnostic.
+    pub ranges: Vec<SourceRange>,
+    /// The fix-its for the diagnostic.
+    pub fix_its: Vec<FixIt>,
+    /// The child diagnostics of the diagnostic (e.g., notes).
+    pub children: Vec<Diagnostic>,
+}
+
+impl Diagnostic {
+    /// Constructs a new `Diagnostic` (and its child diagnostics) from the
+    /// supplied `libclang` diagnostic.
+    ///
+    /// The supplied diagnostic is not disposed of.
+    ///
+    /// # Safety
+    ///
+    /// The supplied diagnostic must be a valid `libclang` diagnostic.
+    pub unsafe fn from_raw(raw: CXDiagnostic) -> Diagnostic {
+        let mut disable = CXString::default();
+        let option = to_option(ClangString::from_raw(clang_getDiagnosticOption(
+            raw,
+            &mut disable,
+        )));
+        let disable_option = to_option(ClangString::from_raw(disable));
+
+        let ranges = (0..clang_getDiagnosticNumRanges(raw))
+            .map(|i| SourceRange::from_raw(clang_getDiagnosticRange(raw, i)))
+            .collect();
+
+        let fix_its = (0..clang_getDiagnosticNumFixIts(raw))
+            .map(|i| {
+                let mut range = CXSourceRange::default();
+                let replacement =
+                    ClangString::from_raw(clang_getDiagnosticFixIt(raw, i, &mut range));
+                FixIt {
+                    range: SourceRange::from_raw(range),
+                    replacement: replacement.into(),
+                }
+            })
+            .collect();
+
+        // The diagnostic set returned by `clang_getChildDiagnostics` is owned by
+        // the diagnostic and must not be disposed of.
+        let children = Diagnostic::from_set(clang_getChildDiagnostics(raw));
+
+        Diagnostic {
+            severity: clang_getDiagnosticSeverity(raw),
+            location: SourceLocation::from_raw(clang_getDiagnosticLocation(raw)),
+            spelling: ClangString::from_raw(clang_getDiagnosticSpelling(raw)).into(),
+            category: ClangString::from_raw(clang_getDiagnosticCategoryText(raw)).into(),
+            option,
+            disable_option,
+            ranges,
+            fix_its,
+            children,
+        }
+    }
+
+    /// Constructs new `Diagnostic`s from the diagnostics in the supplied
+    /// `libclang` diagnostic set.
+    ///
+    /// The supplied diagnostic set is not disposed of.
+    ///
+    /// # Safety
+    ///
+    /// The supplied diagnostic set must be null or a valid `libclang`
+    /// diagnostic set.
+    pub unsafe fn from_set(set: CXDiagnosticSet) -> Vec<Diagnostic> {
+        if set.is_null() {
+            return vec![];
+        }
+
+        (0..clang_getNumDiagnosticsInSet(set))
+            .map(|i| {
+                let raw = clang_getDiagnosticInSet(set, i);
+                let diagnostic = Diagnostic::from_raw(raw);
+                clang_disposeDiagnostic(raw);
+                diagnostic
+            })
+            .collect()
+    }
+
+    /// Returns whether this diagnostic is an error or a fatal error.
+    pub fn is_error(&self) -> bool {
+        self.severity >= CXDiagnostic_Error
+    }
+
+    /// Returns the name of the severity of this diagnostic (e.g., `"fatal
+    /// error"`).
+    pub fn severity_name(&self) -> &'static str {
+        match self.severity {
+            CXDiagnostic_Ignored => "ignored",
+            CXDiagnostic_Note => "note",
+            CXDiagnostic_Warning => "warning",
+            CXDiagnostic_Error => "error",
+            _ => "fatal error",
+        }
+    }
+
+    /// Returns an iterator over this diagnostic and its descendants in
+    /// depth-first order.
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let diagnostic = stack.pop()?;
+            stack.extend(diagnostic.children.iter().rev());
+            Some(diagnostic)
+        })
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}: {}",
+            self.location,
+            self.severity_name(),
+            self.spelling
+        )?;
+        if let Some(option) = &self.option {
+            write!(f, " [{}]", option)?;
+        }
+        Ok(())
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+/// Loads the diagnostics in a serialized diagnostics file (e.g., one created
+/// with `clang --serialize-diagnostics <path>`) with `clang_loadDiagnostics`.
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Diagnostic>, LoadDiagnosticsError> {
+    let path = path.as_ref();
+    let path = path
+        .to_str()
+        .and_then(|p| CString::new(p).ok())
+        .ok_or_else(|| {
+            let message = format!("invalid path: {}", path.display());
+            LoadDiagnosticsError::CannotLoad(message)
+        })?;
+
+    unsafe {
+        let mut error = CXLoadDiag_None;
+        let mut message = CXString::default();
+        let set = clang_loadDiagnostics(path.as_ptr(), &mut error, &mut message);
+        let message = String::from(ClangString::from_raw(message));
+
+        match error {
+            CXLoadDiag_None if !set.is_null() => {
+                let diagnostics = Diagnostic::from_set(set);
+                clang_disposeDiagnosticSet(set);
+                Ok(diagnostics)
+            }
+            CXLoadDiag_CannotLoad => Err(LoadDiagnosticsError::CannotLoad(message)),
+            CXLoadDiag_InvalidFile => Err(LoadDiagnosticsError::InvalidFile(message)),
+            _ => Err(LoadDiagnosticsError::Unknown(message)),
+        }
+    }
+}
+
+/// Converts the supplied string into `None` if it is empty.
+fn to_option(string: ClangString) -> Option<String> {
+    let string = String::from(string);
+    if string.is_empty() {
+        None
+    } else {
+        Some(string)
+    }
+}
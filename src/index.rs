@@ -0,0 +1,272 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Provides owned wrappers for `libclang` indexes and translation units.
+
+use std::error::Error;
+use std::ffi::CString;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr;
+
+use super::diagnostic::Diagnostic;
+use super::string::ClangString;
+use super::*;
+
+//================================================
+// Enums
+//================================================
+
+/// An error encountered while parsing or reparsing a translation unit.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TranslationUnitError {
+    /// An unknown error occurred.
+    Failure,
+    /// `libclang` crashed.
+    Crashed,
+    /// The arguments were invalid (e.g., they contained null characters).
+    InvalidArguments,
+    /// An AST deserialization error occurred.
+    AstReadError,
+}
+
+impl TranslationUnitError {
+    /// Converts a `libclang` error code into an error (`CXError_Success` is
+    /// converted into `Ok(())`).
+    pub fn from_raw(code: CXErrorCode) -> Result<(), TranslationUnitError> {
+        match code {
+            CXError_Success => Ok(()),
+            CXError_Crashed => Err(TranslationUnitError::Crashed),
+            CXError_InvalidArguments => Err(TranslationUnitError::InvalidArguments),
+            CXError_ASTReadError => Err(TranslationUnitError::AstReadError),
+            _ => Err(TranslationUnitError::Failure),
+        }
+    }
+
+    /// Returns the `libclang` error code for this error.
+    pub fn to_raw(self) -> CXErrorCode {
+        match self {
+            TranslationUnitError::Failure => CXError_Failure,
+            TranslationUnitError::Crashed => CXError_Crashed,
+            TranslationUnitError::InvalidArguments => CXError_InvalidArguments,
+            TranslationUnitError::AstReadError => CXError_ASTReadError,
+        }
+    }
+}
+
+impl fmt::Display for TranslationUnitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TranslationUnitError::Failure => write!(f, "an unknown error occurred"),
+            TranslationUnitError::Crashed => write!(f, "`libclang` crashed"),
+            TranslationUnitError::InvalidArguments => write!(f, "the arguments were invalid"),
+            TranslationUnitError::AstReadError => {
+                write!(f, "an AST deserialization error occurred")
+            }
+        }
+    }
+}
+
+impl Error for TranslationUnitError {}
+
+//================================================
+// Structs
+//================================================
+
+/// An owned `libclang` index which is disposed of when dropped.
+#[derive(Debug)]
+pub struct Index {
+    raw: CXIndex,
+}
+
+impl Index {
+    /// Constructs a new `Index` with `clang_createIndex`.
+    ///
+    /// `exclude` determines whether declarations from precompiled headers are
+    /// excluded and `display` determines whether diagnostics are printed.
+    pub fn new(exclude: bool, display: bool) -> Index {
+        let raw = unsafe { clang_createIndex(exclude as c_int, display as c_int) };
+        Index { raw }
+    }
+
+    /// Constructs a new `Index` which takes ownership of the supplied `libclang`
+    /// index.
+    ///
+    /// # Safety
+    ///
+    /// The supplied index must have been returned by a `libclang` function and
+    /// must not be disposed of elsewhere.
+    pub unsafe fn from_raw(raw: CXIndex) -> Index {
+        Index { raw }
+    }
+
+    /// Returns the underlying `libclang` index.
+    pub fn as_raw(&self) -> CXIndex {
+        self.raw
+    }
+}
+
+impl Drop for Index {
+    fn drop(&mut self) {
+        unsafe { clang_disposeIndex(self.raw) };
+    }
+}
+
+/// The contents of a file which has not yet been saved to disk.
+#[derive(Clone, Debug)]
+pub struct Unsaved {
+    path: CString,
+    contents: Vec<u8>,
+}
+
+impl Unsaved {
+    /// Constructs a new `Unsaved` with the supplied path and contents.
+    ///
+    /// # Panics
+    ///
+    /// * `path` contains null characters
+    pub fn new(path: &str, contents: impl Into<Vec<u8>>) -> Unsaved {
+        let path = CString::new(path).expect("path contains null characters");
+        Unsaved {
+            path,
+            contents: contents.into(),
+        }
+    }
+
+    /// Returns a `libclang` unsaved file which borrows from this file.
+    pub fn as_raw(&self) -> CXUnsavedFile {
+        CXUnsavedFile {
+            Filename: self.path.as_ptr(),
+            Contents: self.contents.as_ptr() as *const c_char,
+            Length: self.contents.len() as c_ulong,
+        }
+    }
+}
+
+/// An owned `libclang` translation unit which is disposed of when dropped.
+#[derive(Debug)]
+pub struct TranslationUnit<'i> {
+    raw: CXTranslationUnit,
+    _index: PhantomData<&'i Index>,
+}
+
+impl<'i> TranslationUnit<'i> {
+    /// Parses a source file (and the supplied unsaved files) into a translation
+    /// unit with `clang_parseTranslationUnit2`.
+    ///
+    /// The arguments are the command line arguments that would be passed to
+    /// `clang` (excluding the name of the executable). If `file` is empty, the
+    /// source file must be provided in the arguments instead.
+    pub fn parse(
+        index: &'i Index,
+        file: &str,
+        arguments: &[&str],
+        unsaved: &[Unsaved],
+        flags: CXTranslationUnit_Flags,
+    ) -> Result<TranslationUnit<'i>, TranslationUnitError> {
+        let file = to_c_string(file)?;
+        let arguments = arguments
+            .iter()
+            .map(|a| to_c_string(a))
+            .collect::<Result<Vec<_>, _>>()?;
+        let arguments = arguments.iter().map(|a| a.as_ptr()).collect::<Vec<_>>();
+        let mut unsaved = unsaved.iter().map(|u| u.as_raw()).collect::<Vec<_>>();
+
+        let file = if file.as_bytes().is_empty() {
+            ptr::null()
+        } else {
+            file.as_ptr()
+        };
+
+        unsafe {
+            let mut raw = ptr::null_mut();
+            let code = clang_parseTranslationUnit2(
+                index.raw,
+                file,
+                arguments.as_ptr(),
+                arguments.len() as c_int,
+                unsaved.as_mut_ptr(),
+                unsaved.len() as c_uint,
+                flags,
+                &mut raw,
+            );
+            TranslationUnitError::from_raw(code)?;
+            Ok(TranslationUnit::from_raw(raw))
+        }
+    }
+
+    /// Constructs a new `TranslationUnit` which takes ownership of the supplied
+    /// `libclang` translation unit.
+    ///
+    /// # Safety
+    ///
+    /// The supplied translation unit must have been returned by a `libclang`
+    /// function for an index which outlives `'i` and must not be disposed of
+    /// elsewhere.
+    pub unsafe fn from_raw(raw: CXTranslationUnit) -> TranslationUnit<'i> {
+        TranslationUnit {
+            raw,
+            _index: PhantomData,
+        }
+    }
+
+    /// Returns the underlying `libclang` translation unit.
+    pub fn as_raw(&self) -> CXTranslationUnit {
+        self.raw
+    }
+
+    /// Returns the cursor for this translation unit.
+    pub fn cursor(&self) -> CXCursor {
+        unsafe { clang_getTranslationUnitCursor(self.raw) }
+    }
+
+    /// Returns the name of the source file for this translation unit.
+    pub fn spelling(&self) -> String {
+        unsafe { ClangString::from_raw(clang_getTranslationUnitSpelling(self.raw)).into() }
+    }
+
+    /// Returns the diagnostics for this translation unit.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        unsafe {
+            let set = clang_getDiagnosticSetFromTU(self.raw);
+            let diagnostics = Diagnostic::from_set(set);
+            if !set.is_null() {
+                clang_disposeDiagnosticSet(set);
+            }
+            diagnostics
+        }
+    }
+
+    /// Reparses the source file (and the supplied unsaved files) for this
+    /// translation unit with `clang_reparseTranslationUnit`.
+    ///
+    /// If this returns an error, this translation unit should not be used for
+    /// anything other than being dropped.
+    pub fn reparse(&mut self, unsaved: &[Unsaved]) -> Result<(), TranslationUnitError> {
+        let mut unsaved = unsaved.iter().map(|u| u.as_raw()).collect::<Vec<_>>();
+        unsafe {
+            let flags = clang_defaultReparseOptions(self.raw);
+            let code = clang_reparseTranslationUnit(
+                self.raw,
+                unsaved.len() as c_uint,
+                unsaved.as_mut_ptr(),
+                flags,
+            );
+            TranslationUnitError::from_raw(code)
+        }
+    }
+}
+
+impl<'i> Drop for TranslationUnit<'i> {
+    fn drop(&mut self) {
+        unsafe { clang_disposeTranslationUnit(self.raw) };
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+/// Converts a Rust string into a C string.
+pub(crate) fn to_c_string(string: &str) -> Result<CString, TranslationUnitError> {
+    CString::new(string).map_err(|_| TranslationUnitError::InvalidArguments)
+}
@@ -0,0 +1,573 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Provides a safe interface to the `libclang` indexing API.
+
+use std::any::Any;
+use std::ffi::CStr;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use super::diagnostic::Diagnostic;
+use super::index::{self, Index, TranslationUnit, TranslationUnitError, Unsaved};
+use super::source::SourceLocation;
+use super::*;
+
+//================================================
+// Traits
+//================================================
+
+/// A set of callbacks invoked while indexing a translation unit.
+///
+/// Every method has a default implementation which does nothing, so only the
+/// callbacks of interest need to be implemented. Any panics in these callbacks
+/// abort indexing and are resumed once `libclang` has returned.
+#[allow(unused_variables)]
+pub trait Callbacks {
+    /// Called periodically to determine whether indexing should be aborted.
+    fn abort_query(&mut self) -> bool {
+        false
+    }
+
+    /// Called with the diagnostics produced while indexing.
+    fn on_diagnostics(&mut self, diagnostics: Vec<Diagnostic>) {}
+
+    /// Called when the main file is entered.
+    fn entered_main_file(&mut self, file: CXFile) {}
+
+    /// Called when a file is included (e.g., with `#include`).
+    fn on_include(&mut self, info: IncludedFileInfo<'_>) {}
+
+    /// Called when an AST file (e.g., a PCH file or a module) is imported.
+    fn imported_ast_file(&mut self, info: ImportedAstFileInfo<'_>) {}
+
+    /// Called when indexing of the translation unit starts.
+    fn started_translation_unit(&mut self) {}
+
+    /// Called for each declaration.
+    fn on_declaration(&mut self, info: DeclInfo<'_>) {}
+
+    /// Called for each reference to an entity.
+    fn on_entity_reference(&mut self, info: EntityRefInfo<'_>) {}
+}
+
+//================================================
+// Structs
+//================================================
+
+/// A borrowed view of a `libclang` indexed entity.
+#[derive(Copy, Clone, Debug)]
+pub struct EntityInfo<'a> {
+    raw: &'a CXIdxEntityInfo,
+}
+
+impl<'a> EntityInfo<'a> {
+    /// Constructs a new `EntityInfo` from the supplied `libclang` entity info.
+    ///
+    /// # Safety
+    ///
+    /// The supplied pointer must be null or valid for `'a`.
+    pub unsafe fn from_raw(raw: *const CXIdxEntityInfo) -> Option<EntityInfo<'a>> {
+        raw.as_ref().map(|raw| EntityInfo { raw })
+    }
+
+    /// Returns the underlying `libclang` entity info.
+    pub fn as_raw(&self) -> &'a CXIdxEntityInfo {
+        self.raw
+    }
+
+    /// Returns the kind of this entity (e.g., `CXIdxEntity_Function`).
+    pub fn kind(&self) -> CXIdxEntityKind {
+        self.raw.kind
+    }
+
+    /// Returns the C++ template kind of this entity.
+    pub fn template_kind(&self) -> CXIdxEntityCXXTemplateKind {
+        self.raw.templateKind
+    }
+
+    /// Returns the language of this entity (e.g., `CXIdxEntityLang_C`).
+    pub fn language(&self) -> CXIdxEntityLanguage {
+        self.raw.lang
+    }
+
+    /// Returns the name of this entity, if any.
+    pub fn name(&self) -> Option<&'a str> {
+        unsafe { to_str(self.raw.name) }
+    }
+
+    /// Returns the USR of this entity, if any.
+    pub fn usr(&self) -> Option<&'a str> {
+        unsafe { to_str(self.raw.USR) }
+    }
+
+    /// Returns the cursor for this entity.
+    pub fn cursor(&self) -> CXCursor {
+        self.raw.cursor
+    }
+
+    /// Returns the client data associated with this entity (see
+    /// `set_client_entity`).
+    pub fn client_entity(&self) -> CXIdxClientEntity {
+        unsafe { clang_index_getClientEntity(self.raw) }
+    }
+
+    /// Associates the supplied client data with this entity.
+    ///
+    /// # Safety
+    ///
+    /// The supplied client data is not managed by `libclang` and must remain
+    /// valid for as long as it may be retrieved with `client_entity`.
+    pub unsafe fn set_client_entity(&self, entity: CXIdxClientEntity) {
+        clang_index_setClientEntity(self.raw, entity);
+    }
+}
+
+/// A borrowed view of a `libclang` indexed declaration.
+#[derive(Copy, Clone, Debug)]
+pub struct DeclInfo<'a> {
+    raw: &'a CXIdxDeclInfo,
+}
+
+impl<'a> DeclInfo<'a> {
+    /// Returns the underlying `libclang` declaration info.
+    pub fn as_raw(&self) -> &'a CXIdxDeclInfo {
+        self.raw
+    }
+
+    /// Returns the entity declared by this declaration, if any.
+    pub fn entity(&self) -> Option<EntityInfo<'a>> {
+        unsafe { EntityInfo::from_raw(self.raw.entityInfo) }
+    }
+
+    /// Returns the cursor for this declaration.
+    pub fn cursor(&self) -> CXCursor {
+        self.raw.cursor
+    }
+
+    /// Returns the location of this declaration.
+    pub fn location(&self) -> SourceLocation {
+        to_location(self.raw.loc)
+    }
+
+    /// Returns the cursor for the semantic container of this declaration, if
+    /// any.
+    pub fn semantic_container(&self) -> Option<CXCursor> {
+        unsafe { self.raw.semanticContainer.as_ref().map(|c| c.cursor) }
+    }
+
+    /// Returns the cursor for the lexical container of this declaration, if
+    /// any.
+    pub fn lexical_container(&self) -> Option<CXCursor> {
+        unsafe { self.raw.lexicalContainer.as_ref().map(|c| c.cursor) }
+    }
+
+    /// Returns whether this declaration is a redeclaration.
+    pub fn is_redeclaration(&self) -> bool {
+        self.raw.isRedeclaration != 0
+    }
+
+    /// Returns whether this declaration is a definition.
+    pub fn is_definition(&self) -> bool {
+        self.raw.isDefinition != 0
+    }
+
+    /// Returns whether this declaration is a container (e.g., a struct).
+    pub fn is_container(&self) -> bool {
+        self.raw.isContainer != 0
+    }
+
+    /// Returns whether this declaration is implicit.
+    pub fn is_implicit(&self) -> bool {
+        self.raw.isImplicit != 0
+    }
+}
+
+/// A borrowed view of a `libclang` indexed reference to an entity.
+#[derive(Copy, Clone, Debug)]
+pub struct EntityRefInfo<'a> {
+    raw: &'a CXIdxEntityRefInfo,
+}
+
+impl<'a> EntityRefInfo<'a> {
+    /// Returns the underlying `libclang` entity reference info.
+    pub fn as_raw(&self) -> &'a CXIdxEntityRefInfo {
+        self.raw
+    }
+
+    /// Returns the kind of this reference (e.g., `CXIdxEntityRef_Direct`).
+    pub fn kind(&self) -> CXIdxEntityRefKind {
+        self.raw.kind
+    }
+
+    /// Returns the cursor for this reference.
+    pub fn cursor(&self) -> CXCursor {
+        self.raw.cursor
+    }
+
+    /// Returns the location of this reference.
+    pub fn location(&self) -> SourceLocation {
+        to_location(self.raw.loc)
+    }
+
+    /// Returns the entity referenced by this reference, if any.
+    pub fn referenced_entity(&self) -> Option<EntityInfo<'a>> {
+        unsafe { EntityInfo::from_raw(self.raw.referencedEntity) }
+    }
+
+    /// Returns the entity this reference is in (e.g., the calling function),
+    /// if any.
+    pub fn parent_entity(&self) -> Option<EntityInfo<'a>> {
+        unsafe { EntityInfo::from_raw(self.raw.parentEntity) }
+    }
+
+    /// Returns the cursor for the lexical container of this reference, if any.
+    pub fn container(&self) -> Option<CXCursor> {
+        unsafe { self.raw.container.as_ref().map(|c| c.cursor) }
+    }
+
+    /// Returns the roles of this reference (e.g., `CXSymbolRole_Call`).
+    ///
+    /// Only available on `libclang` 7.0 and later.
+    #[cfg(feature = "clang_7_0")]
+    pub fn role(&self) -> CXSymbolRole {
+        self.raw.role
+    }
+}
+
+/// A borrowed view of a `libclang` included file.
+#[derive(Copy, Clone, Debug)]
+pub struct IncludedFileInfo<'a> {
+    raw: &'a CXIdxIncludedFileInfo,
+}
+
+impl<'a> IncludedFileInfo<'a> {
+    /// Returns the underlying `libclang` included file info.
+    pub fn as_raw(&self) -> &'a CXIdxIncludedFileInfo {
+        self.raw
+    }
+
+    /// Returns the name of the included file as written.
+    pub fn filename(&self) -> Option<&'a str> {
+        unsafe { to_str(self.raw.filename) }
+    }
+
+    /// Returns the included file.
+    pub fn file(&self) -> CXFile {
+        self.raw.file
+    }
+
+    /// Returns the location of the `#` in the inclusion directive.
+    pub fn location(&self) -> SourceLocation {
+        to_location(self.raw.hashLoc)
+    }
+
+    /// Returns whether the inclusion directive is an `#import`.
+    pub fn is_import(&self) -> bool {
+        self.raw.isImport != 0
+    }
+
+    /// Returns whether the included file name is surrounded by angle brackets.
+    pub fn is_angled(&self) -> bool {
+        self.raw.isAngled != 0
+    }
+
+    /// Returns whether the inclusion directive was turned into a module import.
+    pub fn is_module_import(&self) -> bool {
+        self.raw.isModuleImport != 0
+    }
+}
+
+/// A borrowed view of a `libclang` imported AST file.
+#[derive(Copy, Clone, Debug)]
+pub struct ImportedAstFileInfo<'a> {
+    raw: &'a CXIdxImportedASTFileInfo,
+}
+
+impl<'a> ImportedAstFileInfo<'a> {
+    /// Returns the underlying `libclang` imported AST file info.
+    pub fn as_raw(&self) -> &'a CXIdxImportedASTFileInfo {
+        self.raw
+    }
+
+    /// Returns the imported AST file.
+    pub fn file(&self) -> CXFile {
+        self.raw.file
+    }
+
+    /// Returns the imported module, if any.
+    pub fn module(&self) -> CXModule {
+        self.raw.module
+    }
+
+    /// Returns the location where the AST file was imported.
+    pub fn location(&self) -> SourceLocation {
+        to_location(self.raw.loc)
+    }
+
+    /// Returns whether the AST file was imported implicitly.
+    pub fn is_implicit(&self) -> bool {
+        self.raw.isImplicit != 0
+    }
+}
+
+/// An owned `libclang` index action which is disposed of when dropped.
+///
+/// An index action can be used to index multiple translation units, in which
+/// case declarations in headers shared between the translation units are only
+/// indexed once.
+#[derive(Debug)]
+pub struct IndexAction<'i> {
+    raw: CXIndexAction,
+    index: &'i Index,
+}
+
+impl<'i> IndexAction<'i> {
+    /// Constructs a new `IndexAction` for the supplied index with
+    /// `clang_IndexAction_create`.
+    pub fn new(index: &'i Index) -> IndexAction<'i> {
+        let raw = unsafe { clang_IndexAction_create(index.as_raw()) };
+        IndexAction { raw, index }
+    }
+
+    /// Returns the underlying `libclang` index action.
+    pub fn as_raw(&self) -> CXIndexAction {
+        self.raw
+    }
+
+    /// Parses and indexes a source file with `clang_indexSourceFile`, returning
+    /// the parsed translation unit.
+    ///
+    /// The arguments are the same as those for `TranslationUnit::parse`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn index_source_file<C: Callbacks>(
+        &self,
+        callbacks: &mut C,
+        file: &str,
+        arguments: &[&str],
+        unsaved: &[Unsaved],
+        index_flags: CXIndexOptFlags,
+        tu_flags: CXTranslationUnit_Flags,
+    ) -> Result<TranslationUnit<'i>, TranslationUnitError> {
+        let file = index::to_c_string(file)?;
+        let arguments = arguments
+            .iter()
+            .map(|a| index::to_c_string(a))
+            .collect::<Result<Vec<_>, _>>()?;
+        let arguments = arguments.iter().map(|a| a.as_ptr()).collect::<Vec<_>>();
+        let mut unsaved = unsaved.iter().map(|u| u.as_raw()).collect::<Vec<_>>();
+
+        let file = if file.as_bytes().is_empty() {
+            ptr::null()
+        } else {
+            file.as_ptr()
+        };
+
+        let mut raw = ptr::null_mut();
+        let mut state = State {
+            callbacks,
+            panic: None,
+        };
+        let mut table = table::<C>();
+        let code = unsafe {
+            clang_indexSourceFile(
+                self.raw,
+                &mut state as *mut State<C> as CXClientData,
+                &mut table,
+                mem::size_of::<IndexerCallbacks>() as c_uint,
+                index_flags,
+                file,
+                arguments.as_ptr(),
+                arguments.len() as c_int,
+                unsaved.as_mut_ptr(),
+                unsaved.len() as c_uint,
+                &mut raw,
+                tu_flags,
+            )
+        };
+
+        let tu = if raw.is_null() {
+            None
+        } else {
+            Some(unsafe { TranslationUnit::from_raw(raw) })
+        };
+
+        if let Some(payload) = state.panic {
+            panic::resume_unwind(payload);
+        }
+
+        TranslationUnitError::from_raw(code)?;
+        tu.ok_or(TranslationUnitError::Failure)
+    }
+
+    /// Indexes a translation unit which was parsed with the index for this
+    /// index action with `clang_indexTranslationUnit`.
+    pub fn index_translation_unit<C: Callbacks>(
+        &self,
+        callbacks: &mut C,
+        tu: &TranslationUnit<'i>,
+        flags: CXIndexOptFlags,
+    ) -> Result<(), TranslationUnitError> {
+        let mut state = State {
+            callbacks,
+            panic: None,
+        };
+        let mut table = table::<C>();
+        let result = unsafe {
+            clang_indexTranslationUnit(
+                self.raw,
+                &mut state as *mut State<C> as CXClientData,
+                &mut table,
+                mem::size_of::<IndexerCallbacks>() as c_uint,
+                flags,
+                tu.as_raw(),
+            )
+        };
+
+        if let Some(payload) = state.panic {
+            panic::resume_unwind(payload);
+        }
+
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(TranslationUnitError::Failure)
+        }
+    }
+
+    /// Returns the index for this index action.
+    pub fn index(&self) -> &'i Index {
+        self.index
+    }
+}
+
+impl<'i> Drop for IndexAction<'i> {
+    fn drop(&mut self) {
+        unsafe { clang_IndexAction_dispose(self.raw) };
+    }
+}
+
+/// The state shared with the indexer callback trampolines.
+struct State<'c, C> {
+    callbacks: &'c mut C,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+//================================================
+// Functions
+//================================================
+
+/// Returns the supplied C string as a Rust string if it is not null and is
+/// valid UTF-8.
+unsafe fn to_str<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        None
+    } else {
+        CStr::from_ptr(string).to_str().ok()
+    }
+}
+
+/// Returns the expansion location of the supplied `libclang` index location.
+fn to_location(location: CXIdxLoc) -> SourceLocation {
+    unsafe { SourceLocation::from_raw(clang_indexLoc_getCXSourceLocation(location)) }
+}
+
+/// Calls the supplied closure with the callbacks in the supplied state while
+/// catching any panics (the default value is returned after a panic).
+fn call<C, T: Default>(data: CXClientData, f: impl FnOnce(&mut C) -> T) -> T {
+    let state = unsafe { &mut *(data as *mut State<C>) };
+    if state.panic.is_some() {
+        return T::default();
+    }
+
+    // Unwinding across the FFI boundary is undefined behavior, so any panics
+    // are caught here and resumed once `libclang` has returned.
+    match panic::catch_unwind(AssertUnwindSafe(|| f(state.callbacks))) {
+        Ok(value) => value,
+        Err(payload) => {
+            state.panic = Some(payload);
+            T::default()
+        }
+    }
+}
+
+extern "C" fn abort_query<C: Callbacks>(data: CXClientData, _: *mut c_void) -> c_int {
+    let state = unsafe { &*(data as *mut State<C>) };
+    if state.panic.is_some() {
+        return 1;
+    }
+
+    call(data, |c: &mut C| c.abort_query() as c_int)
+}
+
+extern "C" fn diagnostic<C: Callbacks>(data: CXClientData, set: CXDiagnosticSet, _: *mut c_void) {
+    call(data, |c: &mut C| {
+        c.on_diagnostics(unsafe { Diagnostic::from_set(set) })
+    })
+}
+
+extern "C" fn entered_main_file<C: Callbacks>(
+    data: CXClientData,
+    file: CXFile,
+    _: *mut c_void,
+) -> CXIdxClientFile {
+    call(data, |c: &mut C| c.entered_main_file(file));
+    ptr::null_mut()
+}
+
+extern "C" fn included_file<C: Callbacks>(
+    data: CXClientData,
+    info: *const CXIdxIncludedFileInfo,
+) -> CXIdxClientFile {
+    if let Some(raw) = unsafe { info.as_ref() } {
+        call(data, |c: &mut C| c.on_include(IncludedFileInfo { raw }));
+    }
+    ptr::null_mut()
+}
+
+extern "C" fn imported_ast_file<C: Callbacks>(
+    data: CXClientData,
+    info: *const CXIdxImportedASTFileInfo,
+) -> CXIdxClientASTFile {
+    if let Some(raw) = unsafe { info.as_ref() } {
+        call(data, |c: &mut C| {
+            c.imported_ast_file(ImportedAstFileInfo { raw })
+        });
+    }
+    ptr::null_mut()
+}
+
+extern "C" fn started_translation_unit<C: Callbacks>(
+    data: CXClientData,
+    _: *mut c_void,
+) -> CXIdxClientContainer {
+    call(data, |c: &mut C| c.started_translation_unit());
+    ptr::null_mut()
+}
+
+extern "C" fn declaration<C: Callbacks>(data: CXClientData, info: *const CXIdxDeclInfo) {
+    if let Some(raw) = unsafe { info.as_ref() } {
+        call(data, |c: &mut C| c.on_declaration(DeclInfo { raw }));
+    }
+}
+
+extern "C" fn entity_reference<C: Callbacks>(data: CXClientData, info: *const CXIdxEntityRefInfo) {
+    if let Some(raw) = unsafe { info.as_ref() } {
+        call(data, |c: &mut C| {
+            c.on_entity_reference(EntityRefInfo { raw })
+        });
+    }
+}
+
+/// Returns the `libclang` callback table for the supplied callbacks type.
+fn table<C: Callbacks>() -> IndexerCallbacks {
+    IndexerCallbacks {
+        abortQuery: Some(abort_query::<C>),
+        diagnostic: Some(diagnostic::<C>),
+        enteredMainFile: Some(entered_main_file::<C>),
+        ppIncludedFile: Some(included_file::<C>),
+        importedASTFile: Some(imported_ast_file::<C>),
+        startedTranslationUnit: Some(started_translation_unit::<C>),
+        indexDeclaration: Some(declaration::<C>),
+        indexEntityReference: Some(entity_reference::<C>),
+    }
+}
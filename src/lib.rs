@@ -22,7 +22,29 @@
 #![allow(non_camel_case_types, non_snake_case, non_upper_case_globals)]
 #![cfg_attr(feature = "cargo-clippy", allow(clippy::unreadable_literal))]
 
+#[cfg(feature = "safe")]
+pub mod compilation;
+#[cfg(feature = "safe")]
+pub mod completion;
+#[cfg(feature = "safe")]
+pub mod diagnostic;
+#[cfg(feature = "safe")]
+pub mod index;
+#[cfg(feature = "safe")]
+pub mod indexer;
+#[cfg(feature = "report")]
+pub mod report;
+#[cfg(feature = "safe")]
+pub mod source;
+#[cfg(feature = "safe")]
+pub mod string;
 pub mod support;
+#[cfg(feature = "safe")]
+pub mod token;
+#[cfg(feature = "safe")]
+pub mod visit;
+#[cfg(feature = "safe")]
+pub mod xref;
 
 #[macro_use]
 mod link;
@@ -71,6 +93,410 @@ macro_rules! cenum {
 
         $($(#[$vmeta])* pub const $variant: $name = $value;)+
     );
+
+    (@enum #[repr($ty:ty)] $($enum:tt)*) => (
+        cenum! { @typed $ty; $($enum)* }
+    );
+    (@enum $($enum:tt)*) => (
+        cenum! { @typed c_int; $($enum)* }
+    );
+    (@typed $ty:ty; $(#[doc = $doc:literal])* $(#[cfg($cfg:meta)])* $(#[flags])? enum $name:ident {
+        $($(#[doc = $vdoc:literal])* $(#[deprecated = $vdeprecated:literal])? $(#[cfg($vcfg:meta)])*
+        const $variant:ident = $value:expr), +,
+    }) => (
+        cenum! {
+            @generate $ty; [$(#[doc = $doc])*] [$(#[cfg($cfg)])*] $name {
+                $({$(#[doc = $vdoc])* $(#[deprecated = $vdeprecated])?} {$(#[cfg($vcfg)])*} $variant)+
+            }
+        }
+    );
+    (@typed $ty:ty; $(#[doc = $doc:literal])* $(#[cfg($cfg:meta)])* $(#[flags])? enum $name:ident {
+        $($(#[doc = $vdoc:literal])* $(#[deprecated = $vdeprecated:literal])? $(#[cfg($vcfg:meta)])*
+        const $variant:ident = $value:expr); +;
+    }) => (
+        cenum! {
+            @generate $ty; [$(#[doc = $doc])*] [$(#[cfg($cfg)])*] $name {
+                $({$(#[doc = $vdoc])* $(#[deprecated = $vdeprecated])?} {$(#[cfg($vcfg)])*} $variant)+
+            }
+        }
+    );
+    (@generate $ty:ty; [$(#[$doc:meta])*] [$(#[$cfg:meta])*] $name:ident {
+        $({$(#[$vattr:meta])*} {$(#[$vcfg:meta])*} $variant:ident)+
+    }) => (
+        $(#[$doc])*
+        $(#[$cfg])*
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum $name {
+            $($(#[$vattr])* $(#[$vcfg])* $variant,)+
+        }
+
+        $(#[$cfg])*
+        #[allow(deprecated)]
+        impl $name {
+            /// All of the variants of this enum except for aliases.
+            ///
+            /// A variant is an alias if a variant declared after it has the
+            /// same value (e.g., `CXCursor_OMPParallelMaskedDirective` is an
+            /// alias of `CXCursor_TranslationUnit` before `libclang` 15.0).
+            /// Values are always converted into the last variant with the value.
+            pub const ALL: &'static [$name] = &{
+                let mut all = [$name::VARIANTS[0]; $name::UNALIASED];
+                let (mut index, mut count) = (0, 0);
+                while index < $name::VARIANTS.len() {
+                    if !$name::ALIASES[index] {
+                        all[count] = $name::VARIANTS[index];
+                        count += 1;
+                    }
+                    index += 1;
+                }
+                all
+            };
+
+            /// All of the variants of this enum (in declaration order).
+            const VARIANTS: &'static [$name] = &[$($(#[$vcfg])* $name::$variant,)+];
+
+            /// Whether each of the variants of this enum is an alias (see `ALL`).
+            const ALIASES: [bool; $name::VARIANTS.len()] = {
+                let values: &[$ty] = &[$($(#[$vcfg])* super::$variant,)+];
+                let mut aliases = [false; $name::VARIANTS.len()];
+                let mut index = 0;
+                while index < values.len() {
+                    let mut later = index + 1;
+                    while later < values.len() {
+                        if values[index] == values[later] {
+                            aliases[index] = true;
+                        }
+                        later += 1;
+                    }
+                    index += 1;
+                }
+                aliases
+            };
+
+            /// The number of variants of this enum which are not aliases.
+            const UNALIASED: usize = {
+                let (mut index, mut count) = (0, 0);
+                while index < $name::VARIANTS.len() {
+                    if !$name::ALIASES[index] {
+                        count += 1;
+                    }
+                    index += 1;
+                }
+                count
+            };
+
+            /// Returns the name of the constant for this variant.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($(#[$vcfg])* $name::$variant => stringify!($variant),)+
+                }
+            }
+
+            /// Returns the name of the constant with the supplied value, if any.
+            pub fn name_of(value: $ty) -> Option<&'static str> {
+                $name::try_from(value).ok().map($name::name)
+            }
+
+            /// Returns the value of the constant with the supplied name, if any.
+            pub fn value_of(name: &str) -> Option<$ty> {
+                name.parse::<$name>().ok().map(<$ty>::from)
+            }
+        }
+
+        $(#[$cfg])*
+        #[allow(deprecated)]
+        impl From<$name> for $ty {
+            fn from(value: $name) -> $ty {
+                match value {
+                    $($(#[$vcfg])* $name::$variant => super::$variant,)+
+                }
+            }
+        }
+
+        $(#[$cfg])*
+        #[allow(deprecated, unreachable_patterns)]
+        impl TryFrom<$ty> for $name {
+            type Error = UnknownValue;
+
+            fn try_from(value: $ty) -> Result<$name, UnknownValue> {
+                match value {
+                    $($(#[$vcfg])* super::$variant if !$name::ALIASES[$name::$variant as usize] => {
+                        Ok($name::$variant)
+                    })+
+                    _ => Err(UnknownValue { name: stringify!($name), value: value.into() }),
+                }
+            }
+        }
+
+        $(#[$cfg])*
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", self.name())
+            }
+        }
+
+        $(#[$cfg])*
+        #[allow(deprecated)]
+        impl FromStr for $name {
+            type Err = UnknownName;
+
+            fn from_str(name: &str) -> Result<$name, UnknownName> {
+                match name {
+                    $($(#[$vcfg])* stringify!($variant) => Ok($name::$variant),)+
+                    _ => Err(UnknownName { name: stringify!($name), value: name.into() }),
+                }
+            }
+        }
+    );
+
+    (@flags #[repr($ty:ty)] $($enum:tt)*) => (
+        cenum! { @flags_typed $ty; $($enum)* }
+    );
+    (@flags $($enum:tt)*) => (
+        cenum! { @flags_typed c_int; $($enum)* }
+    );
+    (@flags_typed $ty:ty; $(#[doc = $doc:literal])* $(#[cfg($cfg:meta)])* #[flags] enum $name:ident {
+        $($(#[doc = $vdoc:literal])* $(#[cfg($vcfg:meta)])* const $variant:ident = $value:expr); +;
+    }) => (
+        $(#[doc = $doc])*
+        $(#[cfg($cfg)])*
+        #[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+        pub struct $name($ty);
+
+        $(#[cfg($cfg)])*
+        impl $name {
+            $($(#[doc = $vdoc])* $(#[cfg($vcfg)])* pub const $variant: $name = $name(super::$variant);)+
+
+            const FLAGS: &'static [(&'static str, $ty)] = &[
+                $($(#[cfg($vcfg)])* (stringify!($variant), super::$variant),)+
+            ];
+
+            /// Returns an empty set of flags.
+            pub fn empty() -> $name {
+                $name(0)
+            }
+
+            /// Returns the set of flags for the supplied value (unknown bits are
+            /// retained).
+            pub fn from_bits(bits: $ty) -> $name {
+                $name(bits)
+            }
+
+            /// Returns the value for this set of flags.
+            pub fn bits(self) -> $ty {
+                self.0
+            }
+
+            /// Returns whether this set of flags is empty.
+            pub fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+
+            /// Returns whether this set of flags contains all of the supplied flags.
+            pub fn contains(self, other: $name) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Adds the supplied flags to this set of flags.
+            pub fn insert(&mut self, other: $name) {
+                self.0 |= other.0;
+            }
+
+            /// Removes the supplied flags from this set of flags.
+            pub fn remove(&mut self, other: $name) {
+                self.0 &= !other.0;
+            }
+
+            /// Returns an iterator over the named flags in this set of flags.
+            ///
+            /// Flags which combine several bits (e.g.,
+            /// `CXGlobalOpt_ThreadBackgroundPriorityForAll`) are only yielded
+            /// if they contain bits not already yielded.
+            pub fn iter(self) -> impl Iterator<Item = $name> {
+                self.iter_names().map(|(_, flag)| flag)
+            }
+
+            /// Returns an iterator over the names of the constants for and the
+            /// named flags in this set of flags (see `iter`).
+            pub fn iter_names(self) -> impl Iterator<Item = (&'static str, $name)> {
+                let mut remaining = self.0;
+                $name::FLAGS.iter().filter_map(move |&(name, bits)| {
+                    if bits != 0 && self.0 & bits == bits && remaining & bits != 0 {
+                        remaining &= !bits;
+                        Some((name, $name(bits)))
+                    } else {
+                        None
+                    }
+                })
+            }
+        }
+
+        $(#[cfg($cfg)])*
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let mut remaining = self.0;
+                for (index, (name, flag)) in self.iter_names().enumerate() {
+                    if index != 0 {
+                        write!(f, " | ")?;
+                    }
+                    let name = name.split_once('_').map_or(name, |(_, n)| n);
+                    write!(f, "{}", name)?;
+                    remaining &= !flag.0;
+                }
+                match (self.0, remaining) {
+                    (0, _) => write!(f, "(empty)"),
+                    (_, 0) => Ok(()),
+                    (value, _) if value == remaining => write!(f, "{:#x}", remaining),
+                    _ => write!(f, " | {:#x}", remaining),
+                }
+            }
+        }
+
+        $(#[cfg($cfg)])*
+        impl From<$ty> for $name {
+            fn from(bits: $ty) -> $name {
+                $name(bits)
+            }
+        }
+
+        $(#[cfg($cfg)])*
+        impl From<$name> for $ty {
+            fn from(flags: $name) -> $ty {
+                flags.0
+            }
+        }
+
+        $(#[cfg($cfg)])*
+        impl ops::BitOr for $name {
+            type Output = $name;
+
+            fn bitor(self, other: $name) -> $name {
+                $name(self.0 | other.0)
+            }
+        }
+
+        $(#[cfg($cfg)])*
+        impl ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, other: $name) {
+                self.0 |= other.0;
+            }
+        }
+
+        $(#[cfg($cfg)])*
+        impl ops::BitAnd for $name {
+            type Output = $name;
+
+            fn bitand(self, other: $name) -> $name {
+                $name(self.0 & other.0)
+            }
+        }
+    );
+    (@flags_typed $ty:ty; $(#[doc = $doc:literal])* $(#[cfg($cfg:meta)])* enum $name:ident {
+        $($body:tt)*
+    }) => ();
+}
+
+/// Defines a series of C enums (see `cenum!`) and generates a companion Rust
+/// enum for each of them in the `enums` module and a flag set for each of them
+/// marked with `#[flags]` in the `flags` module (other items are passed
+/// through as is).
+///
+/// C enums which are defined as a type alias and constants instead (e.g.,
+/// `CXIndexOptions_Flags`) can be given a flag set with a `cenum! { @flags }`
+/// block which only generates the flag set.
+macro_rules! cenums {
+    (@items [$($enums:tt)*] [$($flags:tt)*] cenum! { @flags $($flag:tt)* } $($rest:tt)*) => (
+        cenums! { @items [$($enums)*] [$($flags)* { $($flag)* }] $($rest)* }
+    );
+    (@items [$($enums:tt)*] [$($flags:tt)*] cenum! { $($enum:tt)* } $($rest:tt)*) => (
+        cenum! { $($enum)* }
+
+        cenums! { @items [$($enums)* { $($enum)* }] [$($flags)* { $($enum)* }] $($rest)* }
+    );
+    (@items [$($enums:tt)*] [$($flags:tt)*] $item:item $($rest:tt)*) => (
+        $item
+
+        cenums! { @items [$($enums)*] [$($flags)*] $($rest)* }
+    );
+    (@items [$({ $($enum:tt)* })+] [$({ $($flag:tt)* })+]) => (
+        /// Rust enums for the C enums in this crate.
+        ///
+        /// Each enum in this module has the same name as the C enum it
+        /// corresponds to (e.g., `enums::CXCursorKind` for `CXCursorKind`) and
+        /// its variants have the same names as the corresponding constants
+        /// (e.g., `enums::CXCursorKind::CXCursor_FunctionDecl` for
+        /// `CXCursor_FunctionDecl`). Values can be converted to and from these
+        /// enums with `From` and `TryFrom` and the names of the constants can
+        /// be looked up with `name_of` and `value_of` (e.g.,
+        /// `enums::CXCursorKind::name_of(clang_getCursorKind(cursor))`).
+        pub mod enums {
+            use std::convert::TryFrom;
+            use std::error::Error;
+            use std::fmt;
+            use std::str::FromStr;
+
+            use super::*;
+
+            /// An error encountered while converting a value into a Rust enum.
+            #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+            pub struct UnknownValue {
+                /// The name of the C enum.
+                pub name: &'static str,
+                /// The value which does not correspond to a variant of the enum.
+                pub value: i64,
+            }
+
+            impl fmt::Display for UnknownValue {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "unknown `{}` value: {}", self.name, self.value)
+                }
+            }
+
+            impl Error for UnknownValue {}
+
+            /// An error encountered while parsing the name of a constant into a
+            /// Rust enum.
+            #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+            pub struct UnknownName {
+                /// The name of the C enum.
+                pub name: &'static str,
+                /// The name which does not correspond to a variant of the enum.
+                pub value: String,
+            }
+
+            impl fmt::Display for UnknownName {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "unknown `{}` name: {:?}", self.name, self.value)
+                }
+            }
+
+            impl Error for UnknownName {}
+
+            $(cenum! { @enum $($enum)* })+
+        }
+
+        /// Flag sets for the C enums in this crate whose constants are combined
+        /// with `|` (e.g., `CXTranslationUnit_Flags`).
+        ///
+        /// Each flag set in this module has the same name as the C enum it
+        /// corresponds to and has an associated constant for each of the
+        /// constants of the C enum (e.g.,
+        /// `flags::CXTranslationUnit_Flags::CXTranslationUnit_KeepGoing`). The
+        /// `Debug` implementations list the flags in a set without the prefixes
+        /// of the constants (e.g., `DetailedPreprocessingRecord | KeepGoing`).
+        pub mod flags {
+            use std::fmt;
+            use std::ops;
+
+            use super::*;
+
+            $(cenum! { @flags $($flag)* })+
+        }
+    );
+    ($($items:tt)+) => (
+        cenums! { @items [] [] $($items)+ }
+    );
 }
 
 /// Implements a zeroing implementation of `Default` for the supplied type.
@@ -97,6 +523,8 @@ macro_rules! default {
 // Enums
 //================================================
 
+cenums! {
+
 cenum! {
     enum CXAvailabilityKind {
         const CXAvailability_Available = 0,
@@ -1254,6 +1682,7 @@ cenum! {
 //================================================
 
 cenum! {
+    #[flags]
     enum CXCodeComplete_Flags {
         const CXCodeComplete_IncludeMacros = 1;
         const CXCodeComplete_IncludeCodePatterns = 2;
@@ -1264,6 +1693,7 @@ cenum! {
 }
 
 cenum! {
+    #[flags]
     enum CXCompletionContext {
         const CXCompletionContext_Unexposed = 0;
         const CXCompletionContext_AnyType = 1;
@@ -1294,6 +1724,7 @@ cenum! {
 }
 
 cenum! {
+    #[flags]
     enum CXDiagnosticDisplayOptions {
         const CXDiagnostic_DisplaySourceLocation = 1;
         const CXDiagnostic_DisplayColumn = 2;
@@ -1305,6 +1736,7 @@ cenum! {
 }
 
 cenum! {
+    #[flags]
     enum CXGlobalOptFlags {
         const CXGlobalOpt_None = 0;
         const CXGlobalOpt_ThreadBackgroundPriorityForIndexing = 1;
@@ -1314,12 +1746,14 @@ cenum! {
 }
 
 cenum! {
+    #[flags]
     enum CXIdxDeclInfoFlags {
         const CXIdxDeclFlag_Skipped = 1;
     }
 }
 
 cenum! {
+    #[flags]
     enum CXIndexOptFlags {
         const CXIndexOptNone = 0;
         const CXIndexOptSuppressRedundantRefs = 1;
@@ -1394,6 +1828,26 @@ pub const CXIndexOptions_DisplayDiagnostics: CXIndexOptions_Flags = 2;
 pub const CXIndexOptions_StorePreamblesInMemory: CXIndexOptions_Flags = 4;
 
 cenum! {
+    @flags
+    #[repr(super::CXIndexOptions_Flags)]
+    /// Only available on `libclang` 17.0 and later.
+    #[cfg(feature = "clang_17_0")]
+    #[flags]
+    enum CXIndexOptions_Flags {
+        /// Only available on `libclang` 17.0 and later.
+        #[cfg(feature = "clang_17_0")]
+        const CXIndexOptions_ExcludeDeclarationsFromPCH = 1;
+        /// Only available on `libclang` 17.0 and later.
+        #[cfg(feature = "clang_17_0")]
+        const CXIndexOptions_DisplayDiagnostics = 2;
+        /// Only available on `libclang` 17.0 and later.
+        #[cfg(feature = "clang_17_0")]
+        const CXIndexOptions_StorePreamblesInMemory = 4;
+    }
+}
+
+cenum! {
+    #[flags]
     enum CXNameRefFlags {
         const CXNameRange_WantQualifier = 1;
         const CXNameRange_WantTemplateArgs = 2;
@@ -1402,6 +1856,7 @@ cenum! {
 }
 
 cenum! {
+    #[flags]
     enum CXObjCDeclQualifierKind {
         const CXObjCDeclQualifier_None = 0;
         const CXObjCDeclQualifier_In = 1;
@@ -1414,6 +1869,7 @@ cenum! {
 }
 
 cenum! {
+    #[flags]
     enum CXObjCPropertyAttrKind {
         const CXObjCPropertyAttr_noattr = 0;
         const CXObjCPropertyAttr_readonly = 1;
@@ -1435,12 +1891,14 @@ cenum! {
 }
 
 cenum! {
+    #[flags]
     enum CXReparse_Flags {
         const CXReparse_None = 0;
     }
 }
 
 cenum! {
+    #[flags]
     enum CXSaveTranslationUnit_Flags {
         const CXSaveTranslationUnit_None = 0;
     }
@@ -1449,6 +1907,7 @@ cenum! {
 cenum! {
     /// Only available on `libclang` 7.0 and later.
     #[cfg(feature = "clang_7_0")]
+    #[flags]
     enum CXSymbolRole {
         const CXSymbolRole_None = 0;
         const CXSymbolRole_Declaration = 1;
@@ -1464,6 +1923,7 @@ cenum! {
 }
 
 cenum! {
+    #[flags]
     enum CXTranslationUnit_Flags {
         const CXTranslationUnit_None = 0;
         const CXTranslationUnit_DetailedPreprocessingRecord = 1;
@@ -1501,6 +1961,8 @@ cenum! {
     }
 }
 
+}
+
 //================================================
 // Structs
 //================================================
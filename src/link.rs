@@ -40,8 +40,11 @@ macro_rules! link {
         )+
     ) => (
         use std::cell::{RefCell};
+        use std::error::Error;
         use std::fmt;
-        use std::sync::{Arc};
+        use std::io;
+        use std::ptr;
+        use std::sync::{Arc, Once, RwLock};
         use std::path::{Path, PathBuf};
 
         /// The (minimum) version of a `libclang` shared library.
@@ -63,6 +66,9 @@ macro_rules! link {
             V12_0 = 120,
             V16_0 = 160,
             V17_0 = 170,
+            V18_0 = 180,
+            V19_0 = 190,
+            V20_0 = 200,
         }
 
         impl fmt::Display for Version {
@@ -83,17 +89,248 @@ macro_rules! link {
                     V11_0 => write!(f, "11.0.x"),
                     V12_0 => write!(f, "12.0.x - 15.0.x"),
                     V16_0 => write!(f, "16.0.x"),
-                    V17_0 => write!(f, "17.0.x or later"),
+                    V17_0 => write!(f, "17.0.x"),
+                    V18_0 => write!(f, "18.0.x"),
+                    V19_0 => write!(f, "19.0.x"),
+                    V20_0 => write!(f, "20.0.x or later"),
                 }
             }
         }
 
+        /// A requirement on the version of a `libclang` shared library.
+        ///
+        /// Versions are compared by their major and minor versions (e.g.,
+        /// `VersionReq::Range((14, 0), (19, 0))` is satisfied by `libclang` 14.0.0
+        /// through 18.1.8 but not by 13.0.1 or 19.1.0).
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+        pub enum VersionReq {
+            /// The version must be the supplied major and minor version or more
+            /// recent.
+            AtLeast(u32, u32),
+            /// The version must be older than the supplied major and minor version.
+            Before(u32, u32),
+            /// The version must have the supplied major and minor version.
+            Exactly(u32, u32),
+            /// The version must be the first supplied major and minor version or
+            /// more recent and must be older than the second.
+            Range((u32, u32), (u32, u32)),
+        }
+
+        impl VersionReq {
+            /// Returns whether the supplied version satisfies this requirement.
+            pub fn matches(&self, version: CXVersion) -> bool {
+                let version = (version.Major as u32, version.Minor as u32);
+                match *self {
+                    VersionReq::AtLeast(major, minor) => version >= (major, minor),
+                    VersionReq::Before(major, minor) => version < (major, minor),
+                    VersionReq::Exactly(major, minor) => version == (major, minor),
+                    VersionReq::Range(minimum, maximum) => version >= minimum && version < maximum,
+                }
+            }
+        }
+
+        impl fmt::Display for VersionReq {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match self {
+                    VersionReq::AtLeast(major, minor) => write!(f, "at least {}.{}", major, minor),
+                    VersionReq::Before(major, minor) => write!(f, "before {}.{}", major, minor),
+                    VersionReq::Exactly(major, minor) => write!(f, "exactly {}.{}", major, minor),
+                    VersionReq::Range((a, b), (c, d)) => {
+                        write!(f, "at least {}.{} and before {}.{}", a, b, c, d)
+                    }
+                }
+            }
+        }
+
+        /// The reason a shared library was rejected while loading a `libclang`
+        /// shared library.
+        #[derive(Debug)]
+        pub enum RejectionReason {
+            /// The shared library could not be read or has an invalid header.
+            Header(io::Error),
+            /// The shared library was built for a different architecture.
+            Architecture(String),
+            /// The shared library could not be opened.
+            Open(libloading::Error),
+            /// The shared library is not a `libclang` shared library.
+            NotLibclang,
+            /// The version of the shared library is too old to be supported by
+            /// this crate (i.e., `3.4` or earlier).
+            Unsupported,
+            /// The version of the shared library does not satisfy the version
+            /// requirement.
+            Version(CXVersion),
+        }
+
+        impl fmt::Display for RejectionReason {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match self {
+                    RejectionReason::Header(error) => write!(f, "{}", error),
+                    RejectionReason::Architecture(message) => write!(f, "{}", message),
+                    RejectionReason::Open(error) => write!(f, "could not be opened: {}", error),
+                    RejectionReason::NotLibclang => write!(f, "missing `clang_createIndex`"),
+                    RejectionReason::Unsupported => write!(f, "unsupported version"),
+                    RejectionReason::Version(version) => {
+                        write!(f, "version {}.{}.{}", version.Major, version.Minor, version.Subminor)
+                    }
+                }
+            }
+        }
+
+        impl From<build::dynamic::InvalidLibrary> for RejectionReason {
+            fn from(invalid: build::dynamic::InvalidLibrary) -> Self {
+                match invalid {
+                    build::dynamic::InvalidLibrary::Header(error) => RejectionReason::Header(error),
+                    build::dynamic::InvalidLibrary::Architecture(message) => {
+                        RejectionReason::Architecture(message)
+                    }
+                }
+            }
+        }
+
+        /// A shared library that was rejected while loading a `libclang` shared
+        /// library.
+        #[derive(Debug)]
+        pub struct Rejection {
+            /// The path to the shared library.
+            pub path: PathBuf,
+            /// The reason the shared library was rejected.
+            pub reason: RejectionReason,
+        }
+
+        impl fmt::Display for Rejection {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "({}: {})", self.path.display(), self.reason)
+            }
+        }
+
+        /// An error encountered while loading or unloading a `libclang` shared
+        /// library.
+        #[derive(Debug)]
+        pub enum LoadError {
+            /// No `libclang` shared libraries could be found.
+            NotFound {
+                /// The filename glob patterns that were searched for.
+                patterns: Vec<String>,
+                /// The directories that were searched.
+                directories: Vec<PathBuf>,
+            },
+            /// `libclang` shared libraries were found but none of them match the
+            /// target platform (e.g., they were built for another architecture).
+            Invalid {
+                /// The `libclang` shared libraries that were found.
+                rejected: Vec<Rejection>,
+            },
+            /// A `libclang` shared library could not be opened.
+            Open {
+                /// The path to the `libclang` shared library.
+                path: PathBuf,
+                /// The error returned when opening the `libclang` shared library.
+                error: libloading::Error,
+            },
+            /// `libclang` shared libraries were found but none of them have a
+            /// version which satisfies the version requirement.
+            Version {
+                /// The version requirement.
+                requirement: VersionReq,
+                /// The `libclang` shared libraries that were found.
+                rejected: Vec<Rejection>,
+            },
+            /// A `libclang` shared library is not in use.
+            NotLoaded,
+        }
+
+        impl fmt::Display for LoadError {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fn join<T: fmt::Display>(items: &[T]) -> String {
+                    items.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", ")
+                }
+
+                match self {
+                    LoadError::NotFound { patterns, directories } => write!(
+                        f,
+                        "couldn't find any shared libraries matching: [{}], set the \
+                         `LIBCLANG_PATH` environment variable to a path where one of these \
+                         files can be found (searched: [{}])",
+                        join(&patterns.iter().map(|p| format!("'{}'", p)).collect::<Vec<_>>()),
+                        join(&directories.iter().map(|d| d.display()).collect::<Vec<_>>()),
+                    ),
+                    LoadError::Invalid { rejected } => write!(
+                        f,
+                        "couldn't find any valid `libclang` shared libraries (invalid: [{}])",
+                        join(rejected),
+                    ),
+                    LoadError::Open { path, error } => write!(
+                        f,
+                        "the `libclang` shared library at {} could not be opened: {}",
+                        path.display(),
+                        error,
+                    ),
+                    LoadError::Version { requirement, rejected } => write!(
+                        f,
+                        "couldn't find a `libclang` shared library with a version {} \
+                         (rejected: [{}])",
+                        requirement,
+                        join(rejected),
+                    ),
+                    LoadError::NotLoaded => write!(f, "a `libclang` shared library is not in use"),
+                }
+            }
+        }
+
+        impl Error for LoadError {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                match self {
+                    LoadError::Open { error, .. } => Some(error),
+                    _ => None,
+                }
+            }
+        }
+
+        /// An error encountered while calling a function in a dynamically loaded
+        /// `libclang` shared library.
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        pub enum CallError {
+            /// A `libclang` shared library is not loaded on this thread or
+            /// globally.
+            NotLoaded,
+            /// The function is not supported by the loaded `libclang` shared
+            /// library.
+            Unsupported {
+                /// The name of the function.
+                function: &'static str,
+                /// The (minimum) version of the loaded `libclang` shared library.
+                version: Option<Version>,
+            },
+        }
+
+        impl fmt::Display for CallError {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match self {
+                    CallError::NotLoaded => write!(f, "a `libclang` shared library is not loaded"),
+                    CallError::Unsupported { function, version: Some(version) } => write!(
+                        f,
+                        "`{}` is not supported by the loaded `libclang` instance ({})",
+                        function,
+                        version,
+                    ),
+                    CallError::Unsupported { function, version: None } => write!(
+                        f,
+                        "`{}` is not supported by the loaded `libclang` instance (unsupported version)",
+                        function,
+                    ),
+                }
+            }
+        }
+
+        impl Error for CallError {}
+
         /// The set of functions loaded dynamically.
         #[derive(Debug, Default)]
         pub struct Functions {
             $(
                 $(#[doc=$doc] #[cfg($cfg)])*
-                pub $name: Option<unsafe extern fn($($pname: $pty), *) $(-> $ret)*>,
+                pub $name: Option<unsafe extern "C" fn($($pname: $pty), *) $(-> $ret)*>,
             )+
         }
 
@@ -125,14 +362,25 @@ macro_rules! link {
             pub fn version(&self) -> Option<Version> {
                 macro_rules! check {
                     ($fn:expr, $version:ident) => {
-                        if self.library.get::<unsafe extern fn()>($fn).is_ok() {
+                        if self.library.get::<unsafe extern "C" fn()>($fn).is_ok() {
                             return Some(Version::$version);
                         }
                     };
                 }
 
                 unsafe {
-                    check!(b"clang_CXXMethod_isExplicit", V17_0);
+                    check!(b"clang_visitCXXBaseClasses", V20_0);
+                    check!(b"clang_Cursor_getBinaryOpcode", V19_0);
+
+                    // `libclang` 18.0 did not add any functions, so the version
+                    // string is used to distinguish it from `libclang` 17.0.
+                    if self.library.get::<unsafe extern "C" fn()>(b"clang_CXXMethod_isExplicit").is_ok() {
+                        return match self.parse_clang_version() {
+                            Some(version) if version.Major >= 18 => Some(Version::V18_0),
+                            _ => Some(Version::V17_0),
+                        };
+                    }
+
                     check!(b"clang_CXXMethod_isCopyAssignmentOperator", V16_0);
                     check!(b"clang_Cursor_getVarDeclInitializer", V12_0);
                     check!(b"clang_Type_getValueType", V11_0);
@@ -151,20 +399,192 @@ macro_rules! link {
 
                 None
             }
+
+            /// Returns the version of this `libclang` shared library.
+            ///
+            /// The version is parsed from the string returned by
+            /// `clang_getClangVersion` (see `support::parse_clang_version`). If
+            /// that string could not be parsed, the (minimum) version returned by
+            /// `version` is returned instead.
+            pub fn clang_version(&self) -> Option<CXVersion> {
+                self.parse_clang_version().or_else(|| {
+                    let version = self.version()? as c_int;
+                    Some(CXVersion { Major: version / 10, Minor: version % 10, Subminor: 0 })
+                })
+            }
+
+            /// Parses the string returned by `clang_getClangVersion` if possible.
+            fn parse_clang_version(&self) -> Option<CXVersion> {
+                unsafe {
+                    let library = &self.library;
+                    let get = library.get::<unsafe extern "C" fn() -> CXString>(b"clang_getClangVersion").ok()?;
+                    let string = library.get::<unsafe extern "C" fn(CXString) -> *const c_char>(b"clang_getCString").ok()?;
+                    let dispose = library.get::<unsafe extern "C" fn(CXString)>(b"clang_disposeString").ok()?;
+
+                    let version = get();
+                    let pointer = string(version);
+                    let parsed = if !pointer.is_null() {
+                        let string = std::ffi::CStr::from_ptr(pointer).to_string_lossy();
+                        crate::support::parse_clang_version(&string)
+                    } else {
+                        None
+                    };
+                    dispose(version);
+
+                    parsed
+                }
+            }
+
+            /// Returns the functions which could not be loaded from this `libclang`
+            /// shared library.
+            ///
+            /// Only the functions enabled by the Cargo features for `libclang`
+            /// versions (e.g., `clang_11_0`) are considered.
+            pub fn missing_functions(&self) -> Vec<MissingFunction> {
+                let mut missing = vec![];
+
+                $(
+                    $(#[cfg($cfg)])*
+                    if self.functions.$name.is_none() {
+                        let cfgs: &[&str] = &[$(stringify!($cfg)), *];
+                        missing.push(MissingFunction::new(stringify!($name), cfgs));
+                    }
+                )+
+
+                missing
+            }
+
+            /// Returns a function from this `libclang` shared library or returns
+            /// an error if the function is not available.
+            ///
+            /// The function is selected from the loaded functions with the
+            /// supplied closure (e.g., `|f| f.clang_createIndex`) and the supplied
+            /// name is used in the error.
+            pub fn function<F>(
+                &self, name: &'static str, select: impl FnOnce(&Functions) -> Option<F>
+            ) -> Result<F, CallError> {
+                select(&self.functions).ok_or_else(|| CallError::Unsupported {
+                    function: name,
+                    version: self.version(),
+                })
+            }
+        }
+
+        /// A function which could not be loaded from a `libclang` shared library.
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+        pub struct MissingFunction {
+            /// The name of this function.
+            pub name: &'static str,
+            /// The minimum `libclang` version required by this function (e.g.,
+            /// `(3, 9)` for `libclang` 3.9).
+            pub minimum_version: (u32, u32),
+        }
+
+        impl MissingFunction {
+            fn new(name: &'static str, cfgs: &[&str]) -> Self {
+                // Extract the minimum version from the Cargo feature which gates
+                // this function (e.g., `feature = "clang_3_9"`), if any.
+                let minimum_version = cfgs
+                    .iter()
+                    .filter_map(|c| c.split("clang_").nth(1))
+                    .filter_map(|v| {
+                        let mut components = v.trim_end_matches('"').split('_');
+                        let major = components.next()?.parse().ok()?;
+                        let minor = components.next()?.parse().ok()?;
+                        Some((major, minor))
+                    })
+                    .max()
+                    .unwrap_or((3, 5));
+                Self { name, minimum_version }
+            }
+        }
+
+        impl fmt::Display for MissingFunction {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let (major, minor) = self.minimum_version;
+                write!(f, "`{}` (requires `libclang` {}.{} or later)", self.name, major, minor)
+            }
+        }
+
+        /// A `libclang` shared library found while searching for `libclang`
+        /// shared libraries.
+        #[derive(Clone, Debug)]
+        pub struct Candidate {
+            /// The directory containing this `libclang` shared library.
+            pub directory: PathBuf,
+            /// The filename of this `libclang` shared library.
+            pub filename: String,
+            /// The version components in the filename of this `libclang` shared
+            /// library (e.g., `[14, 0]` for `libclang-14.0.so`), if any.
+            pub filename_version: Vec<u32>,
+            /// The (minimum) version of this `libclang` shared library as returned
+            /// by `SharedLibrary::version`, or `None` if the version is too old
+            /// to be supported or the shared library could not be opened.
+            pub version: Option<Version>,
+            /// Whether this `libclang` shared library is the one that would be
+            /// loaded by `load` and `load_manually`.
+            pub selected: bool,
+        }
+
+        impl Candidate {
+            /// Returns the path to this `libclang` shared library.
+            pub fn path(&self) -> PathBuf {
+                self.directory.join(&self.filename)
+            }
+
+            /// Loads this `libclang` shared library and returns the library instance.
+            pub fn load(&self) -> Result<SharedLibrary, LoadError> {
+                load_manually_from(self.path())
+            }
         }
 
         thread_local!(static LIBRARY: RefCell<Option<Arc<SharedLibrary>>> = RefCell::new(None));
 
-        /// Returns whether a `libclang` shared library is loaded on this thread.
+        /// Returns the library instance shared by all threads.
+        fn global_library() -> &'static RwLock<Option<Arc<SharedLibrary>>> {
+            static INIT: Once = Once::new();
+            static mut GLOBAL: Option<RwLock<Option<Arc<SharedLibrary>>>> = None;
+
+            unsafe {
+                INIT.call_once(|| GLOBAL = Some(RwLock::new(None)));
+                (*ptr::addr_of!(GLOBAL)).as_ref().unwrap()
+            }
+        }
+
+        /// Returns whether a `libclang` shared library is available on this thread.
+        ///
+        /// This is the case if a library is loaded on this thread or if a library
+        /// has been loaded for all threads with `load_global`.
         pub fn is_loaded() -> bool {
-            LIBRARY.with(|l| l.borrow().is_some())
+            with_library(|_| ()).is_some()
         }
 
-        fn with_library<T, F>(f: F) -> Option<T> where F: FnOnce(&SharedLibrary) -> T {
+        /// Returns a function from the `libclang` shared library in use on this
+        /// thread (see `SharedLibrary::function`).
+        ///
+        /// Unlike calling a function directly (e.g., `clang_createIndex`), this
+        /// function returns an error instead of panicking if a `libclang` shared
+        /// library is not loaded or the function is not available.
+        ///
+        /// ```no_run
+        /// # use clang_sys::*;
+        /// let create = get_function("clang_createIndex", |f| f.clang_createIndex).unwrap();
+        /// let index = unsafe { create(0, 0) };
+        /// ```
+        pub fn get_function<F>(
+            name: &'static str, select: impl FnOnce(&Functions) -> Option<F>
+        ) -> Result<F, CallError> {
+            with_library(|library| library.function(name, select)).unwrap_or(Err(CallError::NotLoaded))
+        }
+
+        fn with_library<T, F>(f: F) -> Option<T> where F: FnOnce(&Arc<SharedLibrary>) -> T {
             LIBRARY.with(|l| {
                 match l.borrow().as_ref() {
                     Some(library) => Some(f(&library)),
-                    _ => None,
+                    _ => {
+                        let global = global_library().read().unwrap_or_else(|e| e.into_inner());
+                        global.as_ref().map(|library| f(&library))
+                    }
                 }
             })
         }
@@ -174,9 +594,11 @@ macro_rules! link {
             #[cfg_attr(feature="cargo-clippy", allow(clippy::too_many_arguments))]
             $(#[doc=$doc] #[cfg($cfg)])*
             pub unsafe fn $name($($pname: $pty), *) $(-> $ret)* {
-                let f = with_library(|library| {
+                // The library instance is kept alive until the function returns
+                // so that it can't be closed by another thread in the meantime.
+                let (_library, f) = with_library(|library| {
                     if let Some(function) = library.functions.$name {
-                        function
+                        (library.clone(), function)
                     } else {
                         panic!(
                             r#"
@@ -198,7 +620,7 @@ https://rust-lang.github.io/rust-bindgen/requirements.html
                                 .unwrap_or_else(|| "unsupported version".into()),
                         );
                     }
-                }).expect("a `libclang` shared library is not loaded on this thread");
+                }).expect("a `libclang` shared library is not loaded on this thread or globally");
                 f($($pname), *)
             }
 
@@ -214,6 +636,47 @@ https://rust-lang.github.io/rust-bindgen/requirements.html
             $(link!(@LOAD: $(#[cfg($cfg)])* fn $name($($pname: $pty), *) $(-> $ret)*);)+
         }
 
+        #[allow(dead_code)]
+        mod build {
+            include!("../build/macros.rs");
+            pub mod common { include!("../build/common.rs"); }
+            pub mod dynamic { include!("../build/dynamic.rs"); }
+        }
+
+        /// Searches for `libclang` shared libraries that match the target platform.
+        fn search() -> Result<build::dynamic::Search, LoadError> {
+            let search = build::dynamic::search(true);
+
+            if !search.valid.is_empty() {
+                Ok(search)
+            } else if !search.invalid.is_empty() {
+                let rejected = search.invalid.into_iter().map(|(path, invalid)| {
+                    Rejection { path, reason: invalid.into() }
+                });
+                Err(LoadError::Invalid { rejected: rejected.collect() })
+            } else {
+                Err(LoadError::NotFound { patterns: search.patterns, directories: search.directories })
+            }
+        }
+
+        /// Opens the `libclang` shared library at the supplied path and loads
+        /// all of the functions in it.
+        fn open(path: &Path) -> Result<SharedLibrary, RejectionReason> {
+            build::dynamic::validate_library(path)?;
+
+            unsafe {
+                let library = libloading::Library::new(path).map_err(RejectionReason::Open)?;
+                let mut library = SharedLibrary::new(library, path.into());
+                $(load::$name(&mut library);)+
+
+                if library.functions.clang_createIndex.is_none() {
+                    return Err(RejectionReason::NotLibclang);
+                }
+
+                Ok(library)
+            }
+        }
+
         /// Loads a `libclang` shared library and returns the library instance.
         ///
         /// This function does not attempt to load any functions from the shared library. The caller
@@ -223,30 +686,107 @@ https://rust-lang.github.io/rust-bindgen/requirements.html
         ///
         /// * a `libclang` shared library could not be found
         /// * the `libclang` shared library could not be opened
-        pub fn load_manually() -> Result<SharedLibrary, String> {
-            #[allow(dead_code)]
-            mod build {
-                include!("../build/macros.rs");
-                pub mod common { include!("../build/common.rs"); }
-                pub mod dynamic { include!("../build/dynamic.rs"); }
-            }
-
-            let (directory, filename) = build::dynamic::find(true)?;
-            let path = directory.join(filename);
+        pub fn load_manually() -> Result<SharedLibrary, LoadError> {
+            let search = search()?;
+            let index = build::dynamic::select(&search.valid).expect("unreachable");
+            let (directory, filename, _) = &search.valid[index];
+            load_manually_from(directory.join(filename))
+        }
 
-            unsafe {
-                let library = libloading::Library::new(&path).map_err(|e| {
-                    format!(
-                        "the `libclang` shared library at {} could not be opened: {}",
-                        path.display(),
-                        e,
-                    )
-                });
+        /// Loads the `libclang` shared library at the supplied path and returns the
+        /// library instance.
+        ///
+        /// Unlike `load_manually`, this function does not search for a `libclang`
+        /// shared library and will only attempt to load the supplied file.
+        ///
+        /// # Failures
+        ///
+        /// * the file does not exist
+        /// * the file does not match the target platform
+        /// * the file could not be opened as a shared library
+        /// * the shared library is not a `libclang` shared library
+        pub fn load_manually_from<P: AsRef<Path>>(path: P) -> Result<SharedLibrary, LoadError> {
+            let path = path.as_ref();
+            open(path).map_err(|reason| match reason {
+                RejectionReason::Header(error) if error.kind() == io::ErrorKind::NotFound => {
+                    let patterns = path.file_name().map(|f| f.to_string_lossy().into_owned());
+                    let directories = path.parent().map(|d| d.to_path_buf());
+                    LoadError::NotFound {
+                        patterns: patterns.into_iter().collect(),
+                        directories: directories.into_iter().collect(),
+                    }
+                }
+                RejectionReason::Open(error) => LoadError::Open { path: path.into(), error },
+                reason => LoadError::Invalid { rejected: vec![Rejection { path: path.into(), reason }] },
+            })
+        }
 
-                let mut library = SharedLibrary::new(library?, path);
-                $(load::$name(&mut library);)+
-                Ok(library)
+        /// Loads the most preferred `libclang` shared library with a version which
+        /// satisfies the supplied requirement and returns the library instance.
+        ///
+        /// The `libclang` shared libraries are searched for in the same way as in
+        /// `load_manually` and are tried in the same order of preference. The
+        /// version of each `libclang` shared library is determined with
+        /// `SharedLibrary::clang_version`.
+        ///
+        /// # Failures
+        ///
+        /// * a `libclang` shared library could not be found
+        /// * none of the `libclang` shared libraries could be opened or had a
+        ///   version which satisfies the supplied requirement
+        pub fn load_manually_matching(requirement: VersionReq) -> Result<SharedLibrary, LoadError> {
+            let mut search = search()?;
+
+            // Sort the `libclang` shared libraries in the same order of
+            // preference used by `build::dynamic::select` (the sort is stable so
+            // the search order is used as a tiebreaker).
+            search.valid.sort_by(|a, b| b.2.cmp(&a.2));
+
+            let mut rejected = search.invalid.into_iter().map(|(path, invalid)| {
+                Rejection { path, reason: invalid.into() }
+            }).collect::<Vec<_>>();
+
+            for (directory, filename, _) in search.valid {
+                let path = directory.join(filename);
+                let reason = match open(&path) {
+                    Ok(library) => match library.clang_version() {
+                        Some(version) if requirement.matches(version) => return Ok(library),
+                        Some(version) => RejectionReason::Version(version),
+                        None => RejectionReason::Unsupported,
+                    },
+                    Err(reason) => reason,
+                };
+
+                rejected.push(Rejection { path, reason });
             }
+
+            Err(LoadError::Version { requirement, rejected })
+        }
+
+        /// Finds all the `libclang` shared libraries that could be loaded.
+        ///
+        /// The `libclang` shared libraries are searched for in the same way as in
+        /// `load_manually` and are returned in the order they were found. Each
+        /// `libclang` shared library is briefly opened to determine its version.
+        ///
+        /// # Failures
+        ///
+        /// * a `libclang` shared library could not be found
+        pub fn find_candidates() -> Result<Vec<Candidate>, LoadError> {
+            let libraries = search()?.valid;
+            let selected = build::dynamic::select(&libraries);
+
+            let candidates = libraries.into_iter().enumerate().map(|(i, library)| {
+                let (directory, filename, filename_version) = library;
+                let path = directory.join(&filename);
+                let version = unsafe { libloading::Library::new(&path) }
+                    .ok()
+                    .and_then(|l| SharedLibrary::new(l, path).version());
+                let selected = Some(i) == selected;
+                Candidate { directory, filename, filename_version, version, selected }
+            });
+
+            Ok(candidates.collect())
         }
 
         /// Loads a `libclang` shared library for use in the current thread.
@@ -261,23 +801,102 @@ https://rust-lang.github.io/rust-bindgen/requirements.html
         /// * a `libclang` shared library could not be found
         /// * the `libclang` shared library could not be opened
         #[allow(dead_code)]
-        pub fn load() -> Result<(), String> {
+        pub fn load() -> Result<(), LoadError> {
             let library = Arc::new(load_manually()?);
             LIBRARY.with(|l| *l.borrow_mut() = Some(library));
             Ok(())
         }
 
+        /// Loads the `libclang` shared library at the supplied path for use in the
+        /// current thread.
+        ///
+        /// This function behaves like `load` except that it does not search for a
+        /// `libclang` shared library and will only attempt to load the supplied file.
+        ///
+        /// # Failures
+        ///
+        /// * the file does not exist
+        /// * the file does not match the target platform
+        /// * the file could not be opened as a shared library
+        /// * the shared library is not a `libclang` shared library
+        pub fn load_from<P: AsRef<Path>>(path: P) -> Result<(), LoadError> {
+            let library = Arc::new(load_manually_from(path)?);
+            LIBRARY.with(|l| *l.borrow_mut() = Some(library));
+            Ok(())
+        }
+
+        /// Loads the most preferred `libclang` shared library with a version which
+        /// satisfies the supplied requirement for use in the current thread.
+        ///
+        /// See `load_manually_matching` for how the `libclang` shared library is
+        /// chosen.
+        ///
+        /// # Failures
+        ///
+        /// * a `libclang` shared library could not be found
+        /// * none of the `libclang` shared libraries could be opened or had a
+        ///   version which satisfies the supplied requirement
+        pub fn load_matching(requirement: VersionReq) -> Result<(), LoadError> {
+            let library = Arc::new(load_manually_matching(requirement)?);
+            LIBRARY.with(|l| *l.borrow_mut() = Some(library));
+            Ok(())
+        }
+
+        /// Loads a `libclang` shared library for use in all threads.
+        ///
+        /// Functions called on a thread which does not have its own library
+        /// instance (i.e., one set by `load` or `set_library`) will use this
+        /// library instance instead. A library instance set for the current
+        /// thread always takes precedence over this library instance.
+        ///
+        /// If a library instance has already been loaded for all threads, it is
+        /// replaced. The replaced library instance is closed once every thread
+        /// that obtained it through `get_global_library` has released it and
+        /// every function called from it has returned.
+        ///
+        /// # Failures
+        ///
+        /// * a `libclang` shared library could not be found
+        /// * the `libclang` shared library could not be opened
+        pub fn load_global() -> Result<(), LoadError> {
+            let library = Arc::new(load_manually()?);
+            set_global_library(Some(library));
+            Ok(())
+        }
+
+        /// Unloads the `libclang` shared library in use in all threads.
+        ///
+        /// This does not affect library instances set for individual threads.
+        /// Threads without such a library instance will no longer be able to
+        /// call `libclang` functions until another library instance is loaded.
+        ///
+        /// # Failures
+        ///
+        /// * a `libclang` shared library is not in use in all threads
+        pub fn unload_global() -> Result<(), LoadError> {
+            let library = set_global_library(None);
+            if library.is_some() {
+                Ok(())
+            } else {
+                Err(LoadError::NotLoaded)
+            }
+        }
+
         /// Unloads the `libclang` shared library in use in the current thread.
         ///
+        /// This does not affect the library instance loaded for all threads by
+        /// `load_global` (if any), which will be used by the current thread
+        /// after this function returns.
+        ///
         /// # Failures
         ///
         /// * a `libclang` shared library is not in use in the current thread
-        pub fn unload() -> Result<(), String> {
+        pub fn unload() -> Result<(), LoadError> {
             let library = set_library(None);
             if library.is_some() {
                 Ok(())
             } else {
-                Err("a `libclang` shared library is not in use in the current thread".into())
+                Err(LoadError::NotLoaded)
             }
         }
 
@@ -294,6 +913,18 @@ https://rust-lang.github.io/rust-bindgen/requirements.html
         pub fn set_library(library: Option<Arc<SharedLibrary>>) -> Option<Arc<SharedLibrary>> {
             LIBRARY.with(|l| mem::replace(&mut *l.borrow_mut(), library))
         }
+
+        /// Returns the library instance used by all threads.
+        pub fn get_global_library() -> Option<Arc<SharedLibrary>> {
+            global_library().read().unwrap_or_else(|e| e.into_inner()).clone()
+        }
+
+        /// Sets the library instance used by all threads and returns the previous
+        /// library.
+        pub fn set_global_library(library: Option<Arc<SharedLibrary>>) -> Option<Arc<SharedLibrary>> {
+            let mut global = global_library().write().unwrap_or_else(|e| e.into_inner());
+            mem::replace(&mut *global, library)
+        }
     )
 }
 
@@ -305,7 +936,7 @@ macro_rules! link {
             pub fn $name:ident($($pname:ident: $pty:ty), *) $(-> $ret:ty)*;
         )+
     ) => (
-        extern {
+        extern "C" {
             $(
                 $(#[doc=$doc] #[cfg($cfg)])*
                 pub fn $name($($pname: $pty), *) $(-> $ret)*;
@@ -0,0 +1,319 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Provides serializers for diagnostics (requires the `report` Cargo feature).
+//!
+//! Diagnostics can be collected from a translation unit with
+//! `TranslationUnit::diagnostics` or from any `libclang` diagnostic set (e.g.,
+//! one returned by `clang_loadDiagnostics`) with `Diagnostic::from_set`.
+
+use std::fmt::{self, Write};
+
+use super::diagnostic::{Diagnostic, FixIt};
+use super::source::{SourceLocation, SourceRange};
+use super::*;
+
+//================================================
+// Enums
+//================================================
+
+/// A JSON value.
+enum Json {
+    Null,
+    Number(u64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn string(string: &str) -> Json {
+        Json::String(string.into())
+    }
+
+    fn optional(string: Option<&str>) -> Json {
+        string.map_or(Json::Null, Json::string)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Number(number) => write!(f, "{}", number),
+            Json::String(string) => {
+                f.write_char('"')?;
+                for c in string.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_char('"')
+            }
+            Json::Array(values) => {
+                f.write_char('[')?;
+                for (index, value) in values.iter().enumerate() {
+                    if index != 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(members) => {
+                f.write_char('{')?;
+                for (index, (key, value)) in members.iter().enumerate() {
+                    if index != 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}:{}", Json::string(key), value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+// JSON __________________________________________
+
+fn json_location(location: &SourceLocation) -> Json {
+    Json::Object(vec![
+        ("file", Json::optional(location.file.as_deref())),
+        ("line", Json::Number(location.line.into())),
+        ("column", Json::Number(location.column.into())),
+        ("offset", Json::Number(location.offset.into())),
+    ])
+}
+
+fn json_range(range: &SourceRange) -> Json {
+    Json::Object(vec![
+        ("start", json_location(&range.start)),
+        ("end", json_location(&range.end)),
+    ])
+}
+
+fn json_fix_it(fix_it: &FixIt) -> Json {
+    Json::Object(vec![
+        ("range", json_range(&fix_it.range)),
+        ("replacement", Json::string(&fix_it.replacement)),
+    ])
+}
+
+fn json_diagnostic(diagnostic: &Diagnostic) -> Json {
+    Json::Object(vec![
+        ("severity", Json::string(diagnostic.severity_name())),
+        ("location", json_location(&diagnostic.location)),
+        ("message", Json::string(&diagnostic.spelling)),
+        ("category", Json::string(&diagnostic.category)),
+        ("option", Json::optional(diagnostic.option.as_deref())),
+        (
+            "disable_option",
+            Json::optional(diagnostic.disable_option.as_deref()),
+        ),
+        (
+            "ranges",
+            Json::Array(diagnostic.ranges.iter().map(json_range).collect()),
+        ),
+        (
+            "fix_its",
+            Json::Array(diagnostic.fix_its.iter().map(json_fix_it).collect()),
+        ),
+        (
+            "children",
+            Json::Array(diagnostic.children.iter().map(json_diagnostic).collect()),
+        ),
+    ])
+}
+
+/// Serializes the supplied diagnostics into a JSON array.
+///
+/// Each diagnostic is serialized as an object with the following members:
+///
+/// * `severity` - `"ignored"`, `"note"`, `"warning"`, `"error"`, or `"fatal error"`
+/// * `location` - a location (see below)
+/// * `message` - the text of the diagnostic
+/// * `category` - the name of the category of the diagnostic
+/// * `option` - the option that enabled the diagnostic (or `null`)
+/// * `disable_option` - the option that disables the diagnostic (or `null`)
+/// * `ranges` - an array of ranges (objects with `start` and `end` locations)
+/// * `fix_its` - an array of objects with `range` and `replacement` members
+/// * `children` - an array of child diagnostics
+///
+/// Locations are objects with `file` (or `null`), `line`, `column`, and
+/// `offset` members.
+pub fn to_json(diagnostics: &[Diagnostic]) -> String {
+    Json::Array(diagnostics.iter().map(json_diagnostic).collect()).to_string()
+}
+
+// SARIF _________________________________________
+
+fn sarif_level(severity: CXDiagnosticSeverity) -> &'static str {
+    match severity {
+        CXDiagnostic_Ignored => "none",
+        CXDiagnostic_Note => "note",
+        CXDiagnostic_Warning => "warning",
+        _ => "error",
+    }
+}
+
+fn sarif_region(start: &SourceLocation, end: Option<&SourceLocation>) -> Json {
+    let mut members = vec![
+        ("startLine", Json::Number(start.line.into())),
+        ("startColumn", Json::Number(start.column.into())),
+    ];
+    if let Some(end) = end {
+        members.push(("endLine", Json::Number(end.line.into())));
+        members.push(("endColumn", Json::Number(end.column.into())));
+    }
+    Json::Object(members)
+}
+
+/// Percent-encodes the supplied path for use in a URI (`/` is not encoded).
+fn sarif_encode(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => write!(encoded, "%{:02X}", byte).unwrap(),
+        }
+    }
+    encoded
+}
+
+/// Returns an artifact location for the supplied file which is a `file` URI if
+/// the path is absolute or a URI relative to `%SRCROOT%` otherwise.
+fn sarif_artifact_location(file: &str) -> Json {
+    let path = file.replace('\\', "/");
+    let bytes = path.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        let uri = format!("file:///{}{}", &path[..2], sarif_encode(&path[2..]));
+        Json::Object(vec![("uri", Json::String(uri))])
+    } else if path.starts_with('/') {
+        let uri = format!("file://{}", sarif_encode(&path));
+        Json::Object(vec![("uri", Json::String(uri))])
+    } else {
+        let uri = sarif_encode(&path);
+        Json::Object(vec![
+            ("uri", Json::String(uri)),
+            ("uriBaseId", Json::string("%SRCROOT%")),
+        ])
+    }
+}
+
+fn sarif_physical_location(location: &SourceLocation) -> Option<Json> {
+    let file = location.file.as_deref()?;
+    Some(Json::Object(vec![
+        ("artifactLocation", sarif_artifact_location(file)),
+        ("region", sarif_region(location, None)),
+    ]))
+}
+
+fn sarif_fix(fix_it: &FixIt) -> Option<Json> {
+    let file = fix_it.range.start.file.as_deref()?;
+    let replacement = Json::Object(vec![
+        (
+            "deletedRegion",
+            sarif_region(&fix_it.range.start, Some(&fix_it.range.end)),
+        ),
+        (
+            "insertedContent",
+            Json::Object(vec![("text", Json::string(&fix_it.replacement))]),
+        ),
+    ]);
+    let change = Json::Object(vec![
+        ("artifactLocation", sarif_artifact_location(file)),
+        ("replacements", Json::Array(vec![replacement])),
+    ]);
+    Some(Json::Object(vec![(
+        "artifactChanges",
+        Json::Array(vec![change]),
+    )]))
+}
+
+fn sarif_result(diagnostic: &Diagnostic) -> Json {
+    let mut members = vec![];
+
+    if let Some(option) = &diagnostic.option {
+        members.push(("ruleId", Json::string(option)));
+    }
+
+    members.push(("level", Json::string(sarif_level(diagnostic.severity))));
+    members.push((
+        "message",
+        Json::Object(vec![("text", Json::string(&diagnostic.spelling))]),
+    ));
+
+    let locations = sarif_physical_location(&diagnostic.location).into_iter();
+    let locations = locations
+        .map(|l| Json::Object(vec![("physicalLocation", l)]))
+        .collect();
+    members.push(("locations", Json::Array(locations)));
+
+    let related = diagnostic.iter().skip(1).enumerate().map(|(index, child)| {
+        let mut members = vec![("id", Json::Number(index as u64))];
+        if let Some(location) = sarif_physical_location(&child.location) {
+            members.push(("physicalLocation", location));
+        }
+        members.push((
+            "message",
+            Json::Object(vec![("text", Json::string(&child.spelling))]),
+        ));
+        Json::Object(members)
+    });
+    members.push(("relatedLocations", Json::Array(related.collect())));
+
+    let fixes = diagnostic.fix_its.iter().filter_map(sarif_fix).collect();
+    members.push(("fixes", Json::Array(fixes)));
+
+    if !diagnostic.category.is_empty() {
+        let properties = vec![("category", Json::string(&diagnostic.category))];
+        members.push(("properties", Json::Object(properties)));
+    }
+
+    Json::Object(members)
+}
+
+/// Serializes the supplied diagnostics into a SARIF 2.1.0 log with a single
+/// run.
+///
+/// Each diagnostic is converted into a result where the rule is the option
+/// that enabled the diagnostic (if any), child diagnostics are converted into
+/// related locations, and fix-its are converted into fixes. Note that columns
+/// are reported as byte offsets (as they are by `libclang`). Files with absolute
+/// paths are referenced with `file` URIs and files with relative paths are
+/// referenced with URIs relative to `%SRCROOT%`.
+pub fn to_sarif(diagnostics: &[Diagnostic]) -> String {
+    let driver = Json::Object(vec![
+        ("name", Json::string("clang")),
+        ("informationUri", Json::string("https://clang.llvm.org")),
+    ]);
+
+    let run = Json::Object(vec![
+        ("tool", Json::Object(vec![("driver", driver)])),
+        (
+            "results",
+            Json::Array(diagnostics.iter().map(sarif_result).collect()),
+        ),
+    ]);
+
+    Json::Object(vec![
+        (
+            "$schema",
+            Json::string("https://json.schemastore.org/sarif-2.1.0.json"),
+        ),
+        ("version", Json::string("2.1.0")),
+        ("runs", Json::Array(vec![run])),
+    ])
+    .to_string()
+}
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Provides owned representations of `libclang` source locations and ranges.
+
+use std::fmt;
+use std::ptr;
+
+use super::string::ClangString;
+use super::*;
+
+//================================================
+// Structs
+//================================================
+
+/// The file, line, column, and offset of a `libclang` source location.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SourceLocation {
+    /// The name of the file (or `None` if the location is not in a file).
+    pub file: Option<String>,
+    /// The line number (starting at `1`).
+    pub line: u32,
+    /// The column number (starting at `1`).
+    pub column: u32,
+    /// The byte offset into the file (starting at `0`).
+    pub offset: u32,
+}
+
+impl SourceLocation {
+    /// Constructs a new `SourceLocation` from the expansion location of the
+    /// supplied `libclang` source location (see `clang_getExpansionLocation`).
+    ///
+    /// # Safety
+    ///
+    /// The supplied source location must be null or must belong to a
+    /// translation unit which has not been disposed of.
+    pub unsafe fn from_raw(raw: CXSourceLocation) -> SourceLocation {
+        let mut file = ptr::null_mut();
+        let mut line = 0;
+        let mut column = 0;
+        let mut offset = 0;
+        clang_getExpansionLocation(raw, &mut file, &mut line, &mut column, &mut offset);
+
+        let file = if file.is_null() {
+            None
+        } else {
+            Some(ClangString::from_raw(clang_getFileName(file)).into())
+        };
+
+        SourceLocation {
+            file,
+            line,
+            column,
+            offset,
+        }
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let file = self.file.as_deref().unwrap_or("<unknown>");
+        write!(f, "{}:{}:{}", file, self.line, self.column)
+    }
+}
+
+/// The start and end of a `libclang` source range.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SourceRange {
+    /// The start of the range (inclusive).
+    pub start: SourceLocation,
+    /// The end of the range (exclusive).
+    pub end: SourceLocation,
+}
+
+impl SourceRange {
+    /// Constructs a new `SourceRange` from the supplied `libclang` source range.
+    ///
+    /// # Safety
+    ///
+    /// The supplied source range must be null or must belong to a translation
+    /// unit which has not been disposed of.
+    pub unsafe fn from_raw(raw: CXSourceRange) -> SourceRange {
+        let start = SourceLocation::from_raw(clang_getRangeStart(raw));
+        let end = SourceLocation::from_raw(clang_getRangeEnd(raw));
+        SourceRange { start, end }
+    }
+}
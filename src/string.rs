@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Provides owned wrappers for `libclang` strings.
+
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::fmt;
+use std::str::Utf8Error;
+
+use super::*;
+
+//================================================
+// Structs
+//================================================
+
+/// An owned `libclang` string which is disposed of when dropped.
+pub struct ClangString {
+    raw: CXString,
+}
+
+impl ClangString {
+    /// Constructs a new `ClangString` which takes ownership of the supplied
+    /// `libclang` string.
+    ///
+    /// # Safety
+    ///
+    /// The supplied string must have been returned by a `libclang` function and
+    /// must not be disposed of elsewhere.
+    pub unsafe fn from_raw(raw: CXString) -> ClangString {
+        ClangString { raw }
+    }
+
+    /// Returns the underlying `libclang` string.
+    ///
+    /// The returned string is only valid as long as this string is alive.
+    pub fn as_raw(&self) -> CXString {
+        self.raw
+    }
+
+    /// Returns the underlying `libclang` string, relinquishing ownership.
+    pub fn into_raw(self) -> CXString {
+        let raw = self.raw;
+        std::mem::forget(self);
+        raw
+    }
+
+    /// Returns whether this string is null (as opposed to empty).
+    pub fn is_null(&self) -> bool {
+        unsafe { clang_getCString(self.raw).is_null() }
+    }
+
+    /// Returns this string as a C string (null strings are returned as empty C
+    /// strings).
+    pub fn as_c_str(&self) -> &CStr {
+        unsafe { to_c_str(self.raw) }
+    }
+
+    /// Returns this string as a Rust string if it is valid UTF-8.
+    pub fn to_str(&self) -> Result<&str, Utf8Error> {
+        self.as_c_str().to_str()
+    }
+
+    /// Returns this string as a Rust string, replacing any invalid UTF-8
+    /// sequences with `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        self.as_c_str().to_string_lossy()
+    }
+}
+
+impl Drop for ClangString {
+    fn drop(&mut self) {
+        unsafe { clang_disposeString(self.raw) };
+    }
+}
+
+impl fmt::Debug for ClangString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.as_c_str())
+    }
+}
+
+impl fmt::Display for ClangString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_string_lossy())
+    }
+}
+
+impl From<ClangString> for String {
+    fn from(string: ClangString) -> String {
+        string.to_string_lossy().into_owned()
+    }
+}
+
+/// An owned set of `libclang` strings which is disposed of when dropped.
+///
+/// Only available on `libclang` 3.8 and later.
+#[cfg(feature = "clang_3_8")]
+pub struct ClangStringSet {
+    raw: *mut CXStringSet,
+}
+
+#[cfg(feature = "clang_3_8")]
+impl ClangStringSet {
+    /// Constructs a new `ClangStringSet` which takes ownership of the supplied
+    /// `libclang` string set (e.g., one returned by
+    /// `clang_Cursor_getCXXManglings`).
+    ///
+    /// # Safety
+    ///
+    /// The supplied string set must be null or must have been returned by a
+    /// `libclang` function and must not be disposed of elsewhere.
+    pub unsafe fn from_raw(raw: *mut CXStringSet) -> ClangStringSet {
+        ClangStringSet { raw }
+    }
+
+    /// Returns the number of strings in this set.
+    pub fn len(&self) -> usize {
+        if self.raw.is_null() {
+            0
+        } else {
+            unsafe { (*self.raw).Count as usize }
+        }
+    }
+
+    /// Returns whether this set contains no strings.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the string at the supplied index in this set, if any.
+    pub fn get(&self, index: usize) -> Option<&CStr> {
+        if index < self.len() {
+            unsafe { Some(to_c_str(*(*self.raw).Strings.add(index))) }
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over the strings in this set.
+    pub fn iter(&self) -> impl Iterator<Item = &CStr> {
+        (0..self.len()).filter_map(move |i| self.get(i))
+    }
+
+    /// Returns the strings in this set as Rust strings, replacing any invalid
+    /// UTF-8 sequences with `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn to_vec_lossy(&self) -> Vec<String> {
+        self.iter()
+            .map(|s| s.to_string_lossy().into_owned())
+            .collect()
+    }
+}
+
+#[cfg(feature = "clang_3_8")]
+impl Drop for ClangStringSet {
+    fn drop(&mut self) {
+        if !self.raw.is_null() {
+            unsafe { clang_disposeStringSet(self.raw) };
+        }
+    }
+}
+
+#[cfg(feature = "clang_3_8")]
+impl fmt::Debug for ClangStringSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+/// Returns the supplied `libclang` string as a C string (null strings are
+/// returned as empty C strings).
+unsafe fn to_c_str<'s>(string: CXString) -> &'s CStr {
+    let pointer = clang_getCString(string);
+    if pointer.is_null() {
+        <&CStr>::default()
+    } else {
+        CStr::from_ptr(pointer)
+    }
+}
@@ -86,7 +86,7 @@ impl Clang {
         }
 
         #[cfg(feature = "runtime")]
-        if let Some(library) = crate::get_library() {
+        if let Some(library) = crate::get_library().or_else(crate::get_global_library) {
             if let Some(directory) = library.path().parent() {
                 paths.push(directory.into());
                 if let Some(parent) = directory.parent() {
@@ -145,6 +145,33 @@ impl Clang {
 // Functions
 //================================================
 
+/// Parses a `clang` version string if possible.
+///
+/// The version string may be the string returned by `clang_getClangVersion` or
+/// the output of `clang --version`. Vendor prefixes and suffixes are ignored,
+/// for example:
+///
+///   * `clang version 17.0.6`
+///   * `Ubuntu clang version 14.0.0-1ubuntu1.1`
+///   * `Apple clang version 15.0.0 (clang-1500.3.9.4)`
+///   * `Android (8490178, based on r450784d) clang version 14.0.6 (...)`
+///
+/// Note that vendors such as Apple use their own version numbers which do not
+/// necessarily match the version of the upstream `clang` release.
+pub fn parse_clang_version(version: &str) -> Option<CXVersion> {
+    version.match_indices("version ").find_map(|(start, _)| {
+        let mut numbers = version[start + 8..].split_whitespace().next()?.split('.');
+        let major = numbers.next().and_then(parse_version_number)?;
+        let minor = numbers.next().and_then(parse_version_number).unwrap_or(0);
+        let subminor = numbers.next().and_then(parse_version_number).unwrap_or(0);
+        Some(CXVersion {
+            Major: major,
+            Minor: minor,
+            Subminor: subminor,
+        })
+    })
+}
+
 /// Returns the first match to the supplied glob patterns in the supplied
 /// directory if there are any matches.
 fn find(directory: &Path, patterns: &[&str]) -> Option<PathBuf> {
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Provides an owned collection of `libclang` tokens.
+
+use std::marker::PhantomData;
+use std::ptr;
+use std::slice;
+
+use super::index::TranslationUnit;
+use super::source::SourceRange;
+use super::string::ClangString;
+use super::*;
+
+//================================================
+// Structs
+//================================================
+
+/// The information in a `libclang` token.
+#[derive(Clone, Debug)]
+pub struct Token {
+    /// The kind of the token (e.g., `CXToken_Identifier`).
+    pub kind: CXTokenKind,
+    /// The text of the token.
+    pub spelling: String,
+    /// The source range covered by the token.
+    pub extent: SourceRange,
+    /// The cursor the token was annotated with (see `Tokens::annotate`), if
+    /// any.
+    pub cursor: Option<CXCursor>,
+}
+
+/// An owned collection of `libclang` tokens which is disposed of when dropped.
+#[derive(Debug)]
+pub struct Tokens<'t> {
+    tu: CXTranslationUnit,
+    raw: *mut CXToken,
+    len: usize,
+    cursors: Option<Vec<CXCursor>>,
+    _tu: PhantomData<&'t ()>,
+}
+
+impl<'t> Tokens<'t> {
+    /// Tokenizes the supplied source range in the supplied translation unit
+    /// with `clang_tokenize`.
+    ///
+    /// # Safety
+    ///
+    /// The supplied source range must belong to the supplied translation unit.
+    pub unsafe fn tokenize<'i>(tu: &'t TranslationUnit<'i>, range: CXSourceRange) -> Tokens<'t> {
+        let mut tokens = Tokens {
+            tu: tu.as_raw(),
+            raw: ptr::null_mut(),
+            len: 0,
+            cursors: None,
+            _tu: PhantomData,
+        };
+        tokens.retokenize(range);
+        tokens
+    }
+
+    /// Replaces the tokens in this collection with the tokens in the supplied
+    /// source range.
+    ///
+    /// Any cursors from a previous call to `annotate` are discarded.
+    ///
+    /// # Safety
+    ///
+    /// The supplied source range must belong to the translation unit of this
+    /// collection.
+    pub unsafe fn retokenize(&mut self, range: CXSourceRange) {
+        self.dispose();
+
+        let mut raw = ptr::null_mut();
+        let mut len = 0;
+        clang_tokenize(self.tu, range, &mut raw, &mut len);
+        self.raw = raw;
+        self.len = len as usize;
+    }
+
+    /// Annotates the tokens in this collection with the cursors they
+    /// correspond to with `clang_annotateTokens`.
+    pub fn annotate(&mut self) {
+        let mut cursors = vec![CXCursor::default(); self.len];
+        if self.len != 0 {
+            unsafe {
+                clang_annotateTokens(self.tu, self.raw, self.len as c_uint, cursors.as_mut_ptr());
+            }
+        }
+        self.cursors = Some(cursors);
+    }
+
+    /// Returns the underlying `libclang` tokens.
+    pub fn as_raw(&self) -> &[CXToken] {
+        if self.raw.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.raw, self.len) }
+        }
+    }
+
+    /// Returns the number of tokens in this collection.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether this collection contains no tokens.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the token at the supplied index in this collection, if any.
+    pub fn get(&self, index: usize) -> Option<Token> {
+        let raw = *self.as_raw().get(index)?;
+        unsafe {
+            Some(Token {
+                kind: clang_getTokenKind(raw),
+                spelling: ClangString::from_raw(clang_getTokenSpelling(self.tu, raw)).into(),
+                extent: SourceRange::from_raw(clang_getTokenExtent(self.tu, raw)),
+                cursor: self.cursors.as_ref().map(|c| c[index]),
+            })
+        }
+    }
+
+    /// Returns an iterator over the tokens in this collection.
+    pub fn iter(&self) -> impl Iterator<Item = Token> + '_ {
+        (0..self.len).filter_map(move |i| self.get(i))
+    }
+
+    /// Disposes of the tokens in this collection.
+    fn dispose(&mut self) {
+        if !self.raw.is_null() {
+            unsafe { clang_disposeTokens(self.tu, self.raw, self.len as c_uint) };
+        }
+
+        self.raw = ptr::null_mut();
+        self.len = 0;
+        self.cursors = None;
+    }
+}
+
+impl<'t> Drop for Tokens<'t> {
+    fn drop(&mut self) {
+        self.dispose();
+    }
+}
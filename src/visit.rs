@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Provides safe helpers for visiting cursors.
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+use super::*;
+
+//================================================
+// Structs
+//================================================
+
+/// A depth-first iterator over the descendants of a cursor.
+///
+/// This iterator yields `(depth, parent, cursor)` tuples where the depth of
+/// the direct children of the root cursor is `1`. Calling `skip_children`
+/// before advancing the iterator prevents the children of the last yielded
+/// cursor from being visited at all.
+///
+/// Since `clang_visitChildren` cannot be suspended, this iterator is only lazy
+/// between cursors. When the iterator advances past a cursor, all of the
+/// direct children of that cursor are collected into a `Vec` (with
+/// `children`) at once. These `Vec`s are kept for every cursor on the current
+/// path, so memory usage is proportional to the number of siblings along the
+/// current path rather than the size of the entire AST.
+///
+/// # Example
+///
+/// ```no_run
+/// # use clang_sys::*;
+/// # use clang_sys::visit::Walk;
+/// # let root: CXCursor = Default::default();
+/// let mut walk = unsafe { Walk::new(root) };
+/// while let Some((depth, _, cursor)) = walk.next() {
+///     if unsafe { clang_Location_isInSystemHeader(clang_getCursorLocation(cursor)) } != 0 {
+///         walk.skip_children();
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Walk {
+    stack: Vec<(usize, CXCursor, Vec<CXCursor>)>,
+    last: Option<(usize, CXCursor)>,
+}
+
+impl Walk {
+    /// Constructs a new `Walk` over the descendants of the supplied cursor.
+    ///
+    /// # Safety
+    ///
+    /// The supplied cursor must belong to a translation unit which is not
+    /// disposed of before this iterator is dropped.
+    pub unsafe fn new(root: CXCursor) -> Walk {
+        Walk {
+            stack: vec![],
+            last: Some((0, root)),
+        }
+    }
+
+    /// Prevents the children of the last cursor yielded by this iterator from
+    /// being visited.
+    pub fn skip_children(&mut self) {
+        self.last = None;
+    }
+}
+
+impl Iterator for Walk {
+    type Item = (usize, CXCursor, CXCursor);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((depth, cursor)) = self.last.take() {
+            // The translation unit outlives this iterator (see `new`).
+            let mut children = unsafe { children(cursor) };
+            children.reverse();
+            self.stack.push((depth + 1, cursor, children));
+        }
+
+        loop {
+            let (depth, parent, children) = self.stack.last_mut()?;
+            if let Some(cursor) = children.pop() {
+                let item = (*depth, *parent, cursor);
+                self.last = Some((item.0, cursor));
+                return Some(item);
+            }
+
+            self.stack.pop();
+        }
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+/// The state shared with `visit_children_trampoline`.
+struct VisitState<F> {
+    f: F,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+/// Calls the closure in the supplied state while catching any panics.
+extern "C" fn visit_children_trampoline<F>(
+    cursor: CXCursor,
+    parent: CXCursor,
+    data: CXClientData,
+) -> CXChildVisitResult
+where
+    F: FnMut(CXCursor, CXCursor) -> CXChildVisitResult,
+{
+    let state = unsafe { &mut *(data as *mut VisitState<F>) };
+
+    // Unwinding across the FFI boundary is undefined behavior, so any panics
+    // are caught here and resumed once `clang_visitChildren` has returned.
+    match panic::catch_unwind(AssertUnwindSafe(|| (state.f)(cursor, parent))) {
+        Ok(result) => result,
+        Err(payload) => {
+            state.panic = Some(payload);
+            CXChildVisit_Break
+        }
+    }
+}
+
+/// Visits the children of the supplied cursor with `clang_visitChildren`,
+/// calling the supplied closure with each visited cursor and its parent.
+///
+/// Returns whether the visitation was terminated early by the closure
+/// returning `CXChildVisit_Break`.
+///
+/// If the closure panics, the visitation is terminated and the panic is
+/// resumed once `clang_visitChildren` has returned.
+///
+/// # Safety
+///
+/// The supplied cursor must belong to a translation unit which has not been
+/// disposed of.
+pub unsafe fn visit_children<F>(cursor: CXCursor, f: F) -> bool
+where
+    F: FnMut(CXCursor, CXCursor) -> CXChildVisitResult,
+{
+    let mut state = VisitState { f, panic: None };
+    let data = &mut state as *mut VisitState<F> as CXClientData;
+    let result = clang_visitChildren(cursor, visit_children_trampoline::<F>, data);
+
+    if let Some(payload) = state.panic {
+        panic::resume_unwind(payload);
+    }
+
+    result != 0
+}
+
+/// Returns a depth-first iterator over the descendants of the supplied cursor
+/// (see `Walk`).
+///
+/// # Safety
+///
+/// The supplied cursor must belong to a translation unit which is not disposed
+/// of before the returned iterator is dropped.
+pub unsafe fn walk(cursor: CXCursor) -> Walk {
+    Walk::new(cursor)
+}
+
+/// Returns the direct children of the supplied cursor.
+///
+/// # Safety
+///
+/// The supplied cursor must belong to a translation unit which has not been
+/// disposed of.
+pub unsafe fn children(cursor: CXCursor) -> Vec<CXCursor> {
+    let mut children = vec![];
+    visit_children(cursor, |c, _| {
+        children.push(c);
+        CXChildVisit_Continue
+    });
+    children
+}
@@ -0,0 +1,451 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Provides a cross-reference database built with the `libclang` indexing API.
+
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use super::index::{TranslationUnit, TranslationUnitError, Unsaved};
+use super::indexer::{Callbacks, DeclInfo, EntityInfo, EntityRefInfo, IndexAction};
+use super::source::SourceLocation;
+use super::string::ClangString;
+use super::*;
+
+/// The first line of a serialized cross-reference database.
+const HEADER: &str = "clang-sys xref 1";
+
+//================================================
+// Enums
+//================================================
+
+/// The kind of an occurrence of a symbol.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum OccurrenceKind {
+    /// A declaration which is not a definition.
+    Declaration,
+    /// A definition.
+    Definition,
+    /// A reference.
+    Reference,
+}
+
+impl OccurrenceKind {
+    fn to_str(self) -> &'static str {
+        match self {
+            OccurrenceKind::Declaration => "declaration",
+            OccurrenceKind::Definition => "definition",
+            OccurrenceKind::Reference => "reference",
+        }
+    }
+
+    fn from_str(string: &str) -> Option<OccurrenceKind> {
+        match string {
+            "declaration" => Some(OccurrenceKind::Declaration),
+            "definition" => Some(OccurrenceKind::Definition),
+            "reference" => Some(OccurrenceKind::Reference),
+            _ => None,
+        }
+    }
+}
+
+//================================================
+// Structs
+//================================================
+
+/// An occurrence of a symbol in a source file.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Occurrence {
+    /// The kind of the occurrence.
+    pub kind: OccurrenceKind,
+    /// The location of the occurrence.
+    pub location: SourceLocation,
+    /// The `CXSymbolRole` bits of the occurrence (only set for references on
+    /// `libclang` 7.0 and later, otherwise `0`).
+    pub role: c_int,
+}
+
+/// A symbol and its occurrences.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Symbol {
+    /// The USR of the symbol.
+    pub usr: String,
+    /// The name of the symbol.
+    pub name: String,
+    /// The kind of the symbol (e.g., `CXIdxEntity_Function`).
+    pub kind: CXIdxEntityKind,
+    /// The occurrences of the symbol in the order they were indexed.
+    pub occurrences: Vec<Occurrence>,
+}
+
+impl Symbol {
+    /// Returns an iterator over the occurrences of this symbol of the supplied
+    /// kind.
+    pub fn occurrences_of(&self, kind: OccurrenceKind) -> impl Iterator<Item = &Occurrence> {
+        self.occurrences.iter().filter(move |o| o.kind == kind)
+    }
+
+    /// Returns an iterator over the declarations of this symbol which are not
+    /// definitions.
+    pub fn declarations(&self) -> impl Iterator<Item = &Occurrence> {
+        self.occurrences_of(OccurrenceKind::Declaration)
+    }
+
+    /// Returns an iterator over the definitions of this symbol.
+    pub fn definitions(&self) -> impl Iterator<Item = &Occurrence> {
+        self.occurrences_of(OccurrenceKind::Definition)
+    }
+
+    /// Returns an iterator over the references to this symbol.
+    pub fn references(&self) -> impl Iterator<Item = &Occurrence> {
+        self.occurrences_of(OccurrenceKind::Reference)
+    }
+}
+
+/// An in-memory cross-reference database of symbols keyed by USR.
+///
+/// Occurrences are deduplicated, so the same header may be indexed as part of
+/// multiple translation units.
+#[derive(Clone, Debug, Default)]
+pub struct Database {
+    symbols: Vec<Symbol>,
+    usrs: HashMap<String, usize>,
+    hashes: HashMap<(usize, u64), usize>,
+}
+
+impl Database {
+    /// Constructs a new empty `Database`.
+    pub fn new() -> Database {
+        Database::default()
+    }
+
+    /// Parses and indexes a source file with the supplied index action, adding
+    /// the symbols and occurrences in it to this database.
+    ///
+    /// The arguments are the same as those for `TranslationUnit::parse`.
+    pub fn index_source_file(
+        &mut self,
+        action: &IndexAction,
+        file: &str,
+        arguments: &[&str],
+        unsaved: &[Unsaved],
+        flags: CXIndexOptFlags,
+    ) -> Result<(), TranslationUnitError> {
+        let mut builder = Builder { database: self };
+        action.index_source_file(&mut builder, file, arguments, unsaved, flags, 0)?;
+        Ok(())
+    }
+
+    /// Indexes a translation unit with the supplied index action, adding the
+    /// symbols and occurrences in it to this database.
+    pub fn index_translation_unit<'i>(
+        &mut self,
+        action: &IndexAction<'i>,
+        tu: &TranslationUnit<'i>,
+        flags: CXIndexOptFlags,
+    ) -> Result<(), TranslationUnitError> {
+        let mut builder = Builder { database: self };
+        action.index_translation_unit(&mut builder, tu, flags)
+    }
+
+    /// Returns the symbol with the supplied USR, if any.
+    pub fn get(&self, usr: &str) -> Option<&Symbol> {
+        self.usrs.get(usr).map(|i| &self.symbols[*i])
+    }
+
+    /// Returns an iterator over the symbols in this database in the order they
+    /// were first indexed.
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter()
+    }
+
+    /// Returns the number of symbols in this database.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Returns whether this database contains no symbols.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Adds the supplied occurrence of the supplied symbol to this database
+    /// (unless it is already present), returning the index of the symbol.
+    ///
+    /// The name and kind are only used if the symbol is not yet present.
+    pub fn insert(
+        &mut self,
+        usr: &str,
+        name: &str,
+        kind: CXIdxEntityKind,
+        occurrence: Occurrence,
+    ) -> usize {
+        let index = self.symbol(usr, name, kind);
+        self.add(index, occurrence);
+        index
+    }
+
+    fn symbol(&mut self, usr: &str, name: &str, kind: CXIdxEntityKind) -> usize {
+        if let Some(index) = self.usrs.get(usr) {
+            return *index;
+        }
+
+        let index = self.symbols.len();
+        self.symbols.push(Symbol {
+            usr: usr.into(),
+            name: name.into(),
+            kind,
+            occurrences: vec![],
+        });
+        self.usrs.insert(usr.into(), index);
+        index
+    }
+
+    fn add(&mut self, index: usize, occurrence: Occurrence) {
+        // Occurrences are looked up by the indices of their symbols and their
+        // hashes so that they don't need to be stored twice. The occurrences of
+        // the symbol are only searched if another occurrence has the same hash.
+        let mut hasher = DefaultHasher::new();
+        occurrence.hash(&mut hasher);
+
+        let occurrences = &mut self.symbols[index].occurrences;
+        let duplicate = match self.hashes.entry((index, hasher.finish())) {
+            Entry::Occupied(entry) => {
+                occurrences[*entry.get()] == occurrence || occurrences.contains(&occurrence)
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(occurrences.len());
+                false
+            }
+        };
+
+        if !duplicate {
+            occurrences.push(occurrence);
+        }
+    }
+
+    /// Serializes this database into the supplied writer.
+    ///
+    /// The format is line-based: a header line, then a `symbol` line for each
+    /// symbol followed by a line for each of its occurrences. Fields are
+    /// separated by tabs and any tabs, newlines, and backslashes in them are
+    /// escaped.
+    pub fn write(&self, writer: impl Write) -> io::Result<()> {
+        let mut writer = BufWriter::new(writer);
+        writeln!(writer, "{}", HEADER)?;
+        for symbol in &self.symbols {
+            writeln!(
+                writer,
+                "symbol\t{}\t{}\t{}",
+                escape(&symbol.usr),
+                escape(&symbol.name),
+                symbol.kind
+            )?;
+            for occurrence in &symbol.occurrences {
+                let location = &occurrence.location;
+                writeln!(
+                    writer,
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    occurrence.kind.to_str(),
+                    occurrence.role,
+                    location.line,
+                    location.column,
+                    location.offset,
+                    location.file.as_deref().map_or("".into(), escape),
+                )?;
+            }
+        }
+        writer.flush()
+    }
+
+    /// Deserializes a database from the supplied reader (see `write`).
+    pub fn read(reader: impl BufRead) -> io::Result<Database> {
+        let mut lines = reader.lines();
+        if lines.next().transpose()?.as_deref() != Some(HEADER) {
+            return Err(invalid("missing or unsupported header"));
+        }
+
+        let mut database = Database::new();
+        let mut current = None;
+        for line in lines {
+            let line = line?;
+            let fields = line.split('\t').map(unescape).collect::<Vec<_>>();
+            match &fields[..] {
+                [tag, usr, name, kind] if tag == "symbol" => {
+                    let kind = kind.parse().map_err(|_| invalid("invalid symbol kind"))?;
+                    current = Some(database.symbol(usr, name, kind));
+                }
+                [kind, role, line, column, offset, file] => {
+                    let index = current.ok_or_else(|| invalid("occurrence without a symbol"))?;
+                    let kind = OccurrenceKind::from_str(kind)
+                        .ok_or_else(|| invalid("invalid occurrence kind"))?;
+                    let file = if file.is_empty() {
+                        None
+                    } else {
+                        Some(file.clone())
+                    };
+                    let location = SourceLocation {
+                        file,
+                        line: parse(line)?,
+                        column: parse(column)?,
+                        offset: parse(offset)?,
+                    };
+                    database.add(
+                        index,
+                        Occurrence {
+                            kind,
+                            location,
+                            role: parse(role)?,
+                        },
+                    );
+                }
+                _ => return Err(invalid("invalid line")),
+            }
+        }
+
+        Ok(database)
+    }
+
+    /// Serializes this database into the file at the supplied path (see
+    /// `write`).
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write(File::create(path)?)
+    }
+
+    /// Deserializes a database from the file at the supplied path (see
+    /// `write`).
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Database> {
+        Database::read(BufReader::new(File::open(path)?))
+    }
+}
+
+/// The indexer callbacks which add symbols and occurrences to a database.
+struct Builder<'d> {
+    database: &'d mut Database,
+}
+
+impl<'d> Builder<'d> {
+    /// Returns the index of the symbol for the supplied entity.
+    ///
+    /// The index is cached in the entity (plus one, so that `0` indicates that
+    /// no index has been cached) so that USRs only need to be looked up once
+    /// per entity while indexing a translation unit.
+    fn symbol(&mut self, entity: EntityInfo) -> Option<usize> {
+        let cached = entity.client_entity() as usize;
+        if cached != 0 {
+            return Some(cached - 1);
+        }
+
+        let usr = match entity.usr() {
+            Some(usr) => usr.into(),
+            None => {
+                String::from(unsafe { ClangString::from_raw(clang_getCursorUSR(entity.cursor())) })
+            }
+        };
+
+        if usr.is_empty() {
+            return None;
+        }
+
+        let index = self
+            .database
+            .symbol(&usr, entity.name().unwrap_or(""), entity.kind());
+        unsafe { entity.set_client_entity((index + 1) as CXIdxClientEntity) };
+        Some(index)
+    }
+}
+
+impl<'d> Callbacks for Builder<'d> {
+    fn on_declaration(&mut self, info: DeclInfo) {
+        if let Some(index) = info.entity().and_then(|e| self.symbol(e)) {
+            let kind = if info.is_definition() {
+                OccurrenceKind::Definition
+            } else {
+                OccurrenceKind::Declaration
+            };
+            self.database.add(
+                index,
+                Occurrence {
+                    kind,
+                    location: info.location(),
+                    role: 0,
+                },
+            );
+        }
+    }
+
+    fn on_entity_reference(&mut self, info: EntityRefInfo) {
+        if let Some(index) = info.referenced_entity().and_then(|e| self.symbol(e)) {
+            let kind = OccurrenceKind::Reference;
+            self.database.add(
+                index,
+                Occurrence {
+                    kind,
+                    location: info.location(),
+                    role: role(&info),
+                },
+            );
+        }
+    }
+}
+
+//================================================
+// Functions
+//================================================
+
+#[cfg(feature = "clang_7_0")]
+fn role(info: &EntityRefInfo) -> c_int {
+    info.role()
+}
+
+#[cfg(not(feature = "clang_7_0"))]
+fn role(_: &EntityRefInfo) -> c_int {
+    0
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn parse<T: std::str::FromStr>(field: &str) -> io::Result<T> {
+    field.parse().map_err(|_| invalid("invalid number"))
+}
+
+/// Escapes any tabs, newlines, and backslashes in the supplied field.
+fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\\' => escaped.push_str("\\\\"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Unescapes a field escaped with `escape`.
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
@@ -4,6 +4,12 @@ use clang_sys::*;
 
 use libc::c_char;
 
+/// Loads `libclang` for use in the current thread if linking at runtime.
+fn init() {
+    #[cfg(feature = "runtime")]
+    load().unwrap();
+}
+
 fn parse() {
     unsafe {
         let index = clang_createIndex(0, 0);
@@ -32,12 +38,1151 @@ fn test() {
     unload().unwrap();
 }
 
+#[cfg(feature = "runtime")]
+#[test]
+fn test_global() {
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    load_global().unwrap();
+    assert!(get_library().is_none());
+    assert!(is_loaded());
+
+    // Worker threads in a pool should be able to call `libclang` functions
+    // without loading a library instance themselves.
+    let (sender, receiver) = mpsc::channel::<()>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    let workers = (0..4)
+        .map(|_| {
+            let receiver = receiver.clone();
+            thread::spawn(move || {
+                let mut jobs = 0;
+                while receiver.lock().unwrap().recv().is_ok() {
+                    assert!(is_loaded());
+                    assert!(get_library().is_none());
+                    assert!(clang_createIndex::is_loaded());
+                    parse();
+                    jobs += 1;
+                }
+                jobs
+            })
+        })
+        .collect::<Vec<_>>();
+    for _ in 0..16 {
+        sender.send(()).unwrap();
+    }
+    drop(sender);
+    let jobs: usize = workers.into_iter().map(|w| w.join().unwrap()).sum();
+    assert_eq!(jobs, 16);
+
+    // A library instance loaded for a thread takes precedence over the global
+    // library instance and unloading it falls back to the global instance.
+    thread::spawn(|| {
+        load().unwrap();
+        let global = get_global_library().unwrap();
+        assert!(!Arc::ptr_eq(&get_library().unwrap(), &global));
+        unload().unwrap();
+        assert!(is_loaded());
+        parse();
+    })
+    .join()
+    .unwrap();
+
+    unload_global().unwrap();
+    assert!(unload_global().is_err());
+    assert!(!is_loaded());
+    thread::spawn(|| assert!(!is_loaded())).join().unwrap();
+}
+
+#[cfg(feature = "runtime")]
+#[test]
+fn test_load_from() {
+    let path = load_manually().unwrap().path().to_owned();
+    load_from(&path).unwrap();
+    assert_eq!(get_library().unwrap().path(), path);
+    parse();
+    unload().unwrap();
+}
+
+#[cfg(feature = "runtime")]
+#[test]
+fn test_load_from_invalid() {
+    match load_manually_from("tests/header.h") {
+        Err(LoadError::Invalid { rejected }) => {
+            assert_eq!(rejected.len(), 1);
+            assert_eq!(rejected[0].path, std::path::Path::new("tests/header.h"));
+            assert!(matches!(rejected[0].reason, RejectionReason::Header(_)));
+        }
+        result => panic!("expected invalid error, received: {:?}", result),
+    }
+
+    match load_manually_from("tests/missing.so") {
+        Err(LoadError::NotFound {
+            patterns,
+            directories,
+        }) => {
+            assert_eq!(patterns, ["missing.so"]);
+            assert_eq!(directories, [std::path::Path::new("tests")]);
+        }
+        result => panic!("expected not found error, received: {:?}", result),
+    }
+
+    assert!(load_from("tests/header.h").is_err());
+    assert!(get_library().is_none());
+    assert!(matches!(unload(), Err(LoadError::NotLoaded)));
+}
+
+#[cfg(feature = "runtime")]
+#[test]
+fn test_find_candidates() {
+    let candidates = find_candidates().unwrap();
+    for candidate in &candidates {
+        println!("{:?}", candidate);
+    }
+
+    let selected = candidates.iter().filter(|c| c.selected).collect::<Vec<_>>();
+    assert_eq!(selected.len(), 1);
+    assert_eq!(selected[0].path(), load_manually().unwrap().path());
+    assert_eq!(selected[0].load().unwrap().path(), selected[0].path());
+}
+
+#[cfg(feature = "runtime")]
+#[test]
+fn test_load_matching() {
+    let library = load_manually().unwrap();
+    let version = library.clang_version().unwrap();
+    let (major, minor) = (version.Major as u32, version.Minor as u32);
+
+    let matching = load_manually_matching(VersionReq::Exactly(major, minor)).unwrap();
+    assert_eq!(
+        matching.clang_version().map(|v| (v.Major, v.Minor)),
+        Some((version.Major, version.Minor))
+    );
+    let matching = load_manually_matching(VersionReq::Range((3, 5), (major + 1, 0))).unwrap();
+    assert_eq!(
+        matching.clang_version().map(|v| (v.Major, v.Minor)),
+        Some((version.Major, version.Minor))
+    );
+
+    match load_manually_matching(VersionReq::Before(3, 5)) {
+        Err(LoadError::Version { rejected, .. }) => {
+            let rejection = rejected.iter().find(|r| r.path == library.path()).unwrap();
+            assert!(
+                matches!(rejection.reason, RejectionReason::Version(v) if v.Major == version.Major)
+            );
+        }
+        result => panic!("expected version error, received: {:?}", result),
+    }
+
+    load_matching(VersionReq::AtLeast(3, 5)).unwrap();
+    parse();
+    unload().unwrap();
+}
+
+#[cfg(feature = "runtime")]
+#[test]
+fn test_version_req() {
+    let version = |major, minor, subminor| CXVersion {
+        Major: major,
+        Minor: minor,
+        Subminor: subminor,
+    };
+
+    let requirement = VersionReq::Range((14, 0), (19, 0));
+    assert!(requirement.matches(version(14, 0, 6)));
+    assert!(requirement.matches(version(18, 1, 8)));
+    assert!(!requirement.matches(version(13, 0, 1)));
+    assert!(!requirement.matches(version(19, 1, 0)));
+    assert_eq!(requirement.to_string(), "at least 14.0 and before 19.0");
+
+    assert!(VersionReq::AtLeast(14, 0).matches(version(14, 0, 0)));
+    assert!(!VersionReq::AtLeast(14, 0).matches(version(13, 0, 1)));
+    assert!(VersionReq::Before(14, 0).matches(version(13, 0, 1)));
+    assert!(!VersionReq::Before(14, 0).matches(version(14, 0, 0)));
+    assert!(VersionReq::Exactly(14, 0).matches(version(14, 0, 6)));
+    assert!(!VersionReq::Exactly(14, 0).matches(version(15, 0, 0)));
+}
+
+#[cfg(feature = "runtime")]
+#[test]
+fn test_get_function() {
+    use std::sync::Arc;
+
+    let mut library = load_manually().unwrap();
+    let version = library.version();
+    library.functions.clang_disposeIndex = None;
+    let error = CallError::Unsupported {
+        function: "clang_disposeIndex",
+        version,
+    };
+    assert_eq!(
+        library
+            .function("clang_disposeIndex", |f| f.clang_disposeIndex)
+            .unwrap_err(),
+        error
+    );
+    set_library(Some(Arc::new(library)));
+
+    let create = get_function("clang_createIndex", |f| f.clang_createIndex).unwrap();
+    assert!(!unsafe { create(0, 0) }.is_null());
+    assert_eq!(
+        get_function("clang_disposeIndex", |f| f.clang_disposeIndex).unwrap_err(),
+        error
+    );
+
+    unload().unwrap();
+}
+
+#[cfg(feature = "runtime")]
+#[test]
+fn test_missing_functions() {
+    let mut library = load_manually().unwrap();
+    for function in library.missing_functions() {
+        println!("{}", function);
+    }
+
+    library.functions.clang_disposeIndex = None;
+    let missing = library.missing_functions();
+    let function = MissingFunction {
+        name: "clang_disposeIndex",
+        minimum_version: (3, 5),
+    };
+    assert!(missing.contains(&function));
+
+    #[cfg(feature = "clang_3_8")]
+    {
+        library.functions.clang_CXXField_isMutable = None;
+        let missing = library.missing_functions();
+        let function = MissingFunction {
+            name: "clang_CXXField_isMutable",
+            minimum_version: (3, 8),
+        };
+        assert!(missing.contains(&function));
+    }
+}
+
 #[cfg(not(feature = "runtime"))]
 #[test]
 fn test() {
     parse();
 }
 
+#[cfg(feature = "safe")]
+#[test]
+fn test_string() {
+    init();
+
+    unsafe {
+        let version = string::ClangString::from_raw(clang_getClangVersion());
+        assert!(!version.is_null());
+        assert!(version.to_str().unwrap().contains("version"));
+
+        let spelling =
+            string::ClangString::from_raw(clang_getCursorKindSpelling(CXCursor_FunctionDecl));
+        assert_eq!(spelling.as_c_str().to_bytes(), b"FunctionDecl");
+        assert_eq!(spelling.to_string_lossy(), "FunctionDecl");
+        assert_eq!(String::from(spelling), "FunctionDecl");
+    }
+
+    #[cfg(feature = "clang_3_8")]
+    unsafe {
+        let set = string::ClangStringSet::from_raw(ptr::null_mut());
+        assert!(set.is_empty());
+        assert_eq!(set.get(0), None);
+        assert!(set.to_vec_lossy().is_empty());
+    }
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn test_index() {
+    use clang_sys::index::*;
+
+    init();
+
+    let index = Index::new(false, false);
+    let mut tu = TranslationUnit::parse(&index, "tests/header.h", &[], &[], 0).unwrap();
+    assert!(tu.spelling().ends_with("header.h"));
+    assert_eq!(
+        unsafe { clang_getCursorKind(tu.cursor()) },
+        CXCursor_TranslationUnit
+    );
+    tu.reparse(&[]).unwrap();
+
+    let unsaved = &[Unsaved::new("unsaved.c", "int subtract(int a, int b);")];
+    let tu = TranslationUnit::parse(&index, "unsaved.c", &["-std=c99"], unsaved, 0).unwrap();
+    assert_eq!(tu.spelling(), "unsaved.c");
+
+    let result = TranslationUnit::parse(&index, "tests/header.h", &["-D\0"], &[], 0);
+    assert_eq!(result.unwrap_err(), TranslationUnitError::InvalidArguments);
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn test_indexer() {
+    use clang_sys::index::*;
+    use clang_sys::indexer::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        started: bool,
+        declarations: Vec<(String, String, u32, bool)>,
+        references: Vec<(String, u32)>,
+    }
+
+    impl Callbacks for Recorder {
+        fn started_translation_unit(&mut self) {
+            self.started = true;
+        }
+
+        fn on_declaration(&mut self, info: DeclInfo) {
+            let entity = info.entity().unwrap();
+            let name = entity.name().unwrap().into();
+            let usr = entity.usr().unwrap().into();
+            self.declarations
+                .push((name, usr, info.location().line, info.is_definition()));
+        }
+
+        fn on_entity_reference(&mut self, info: EntityRefInfo) {
+            let name = info.referenced_entity().unwrap().name().unwrap().into();
+            self.references.push((name, info.location().line));
+        }
+    }
+
+    init();
+
+    let source = "#include \"header.h\"\nint twice(int a) { return add(a, a); }\n";
+    let unsaved = &[Unsaved::new("indexer.c", source)];
+
+    let index = Index::new(false, false);
+    let action = IndexAction::new(&index);
+    let mut recorder = Recorder::default();
+    let tu = action
+        .index_source_file(&mut recorder, "indexer.c", &["-Itests"], unsaved, 0, 0)
+        .unwrap();
+    assert!(recorder.started);
+    assert!(recorder
+        .declarations
+        .contains(&("add".into(), "c:@F@add".into(), 4, false)));
+    assert!(recorder
+        .declarations
+        .contains(&("twice".into(), "c:@F@twice".into(), 2, true)));
+    assert!(recorder.references.contains(&("add".into(), 2)));
+
+    let mut recorder = Recorder::default();
+    action
+        .index_translation_unit(&mut recorder, &tu, 0)
+        .unwrap();
+    assert!(recorder.declarations.iter().any(|d| d.0 == "twice"));
+
+    struct Panicker;
+
+    impl Callbacks for Panicker {
+        fn on_declaration(&mut self, _: DeclInfo) {
+            panic!("declaration");
+        }
+    }
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _ = action.index_translation_unit(&mut Panicker, &tu, 0);
+    }));
+    assert_eq!(
+        *result.unwrap_err().downcast::<&str>().unwrap(),
+        "declaration"
+    );
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn test_compilation() {
+    use std::fs;
+    use std::path::Path;
+
+    use clang_sys::compilation::*;
+    #[cfg(feature = "clang_3_8")]
+    use clang_sys::index::*;
+
+    init();
+
+    let tests = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let tmp = tempfile::Builder::new()
+        .prefix("clang_sys_test")
+        .tempdir()
+        .unwrap();
+    let json = format!(
+        r#"[{{"directory": "{}", "arguments": ["clang", "-c", "-xc", "-DVALUE=1", "--", "header.h"], "file": "header.h"}}]"#,
+        tests.display().to_string().replace('\\', "\\\\"),
+    );
+    fs::write(tmp.path().join("compile_commands.json"), json).unwrap();
+
+    let database = CompilationDatabase::from_directory(tmp.path()).unwrap();
+    let commands = database.all_compile_commands();
+    assert_eq!(commands.len(), 1);
+    assert!(commands.get(1).is_none());
+
+    let command = commands.get(0).unwrap();
+    assert_eq!(Path::new(&command.directory()), tests);
+    let arguments = command.arguments().collect::<Vec<_>>();
+    assert_eq!(arguments.first().map(|a| &a[..]), Some("clang"));
+    assert!(arguments.iter().any(|a| a == "-DVALUE=1"));
+    assert_eq!(command.num_arguments(), arguments.len());
+
+    assert_eq!(
+        database
+            .compile_commands(tests.join("header.h").to_str().unwrap())
+            .len(),
+        1
+    );
+    assert!(database.compile_commands("missing.c").is_empty());
+
+    #[cfg(feature = "clang_3_8")]
+    {
+        let index = Index::new(false, false);
+        let tu = command.parse(&index, &[], 0).unwrap();
+        assert!(tu.spelling().ends_with("header.h"));
+    }
+
+    let result = CompilationDatabase::from_directory(tmp.path().join("missing"));
+    assert_eq!(
+        result.unwrap_err(),
+        CompilationDatabaseError::CanNotLoadDatabase
+    );
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn test_completion() {
+    use clang_sys::completion::*;
+    use clang_sys::flags;
+    use clang_sys::index::*;
+
+    init();
+
+    let source = "struct S { int alpha; int beta; };\nvoid f(struct S s) { s. }\n";
+    let unsaved = &[Unsaved::new("completion.c", source)];
+    let index = Index::new(false, false);
+    let tu = TranslationUnit::parse(&index, "completion.c", &[], unsaved, 0).unwrap();
+
+    let flags = unsafe { clang_defaultCodeCompleteOptions() };
+    let mut completion =
+        CodeCompletion::complete(&tu, "completion.c", 2, 24, unsaved, flags).unwrap();
+    completion.sort();
+
+    let results = completion.iter().collect::<Vec<_>>();
+    let typed = results
+        .iter()
+        .filter_map(|r| r.string.typed_text())
+        .collect::<Vec<_>>();
+    assert_eq!(typed, &["alpha", "beta"]);
+
+    let alpha = &results[0];
+    assert_eq!(alpha.cursor_kind, CXCursor_FieldDecl);
+    assert_eq!(alpha.string.availability, CXAvailability_Available);
+    assert!(alpha.fix_its.is_empty());
+    let kinds = alpha
+        .string
+        .chunks
+        .iter()
+        .map(|c| c.kind())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        &[CXCompletionChunk_ResultType, CXCompletionChunk_TypedText]
+    );
+
+    let contexts = completion.contexts();
+    assert!(contexts.contains(flags::CXCompletionContext::CXCompletionContext_DotMemberAccess));
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn test_completion_render() {
+    use clang_sys::completion::*;
+    use clang_sys::index::*;
+
+    init();
+
+    let source = "#include \"header.h\"\nint f(void) { return ad }\n";
+    let unsaved = &[Unsaved::new("render.c", source)];
+    let index = Index::new(false, false);
+    let tu = TranslationUnit::parse(&index, "render.c", &["-Itests"], unsaved, 0).unwrap();
+
+    let flags = unsafe { clang_defaultCodeCompleteOptions() };
+    let completion = CodeCompletion::complete(&tu, "render.c", 2, 24, unsaved, flags).unwrap();
+    let add = completion
+        .iter()
+        .find(|r| r.string.typed_text() == Some("add"))
+        .unwrap();
+    assert_eq!(add.string.label(), "add(int a, int b)");
+    assert_eq!(add.string.filter_text(), "add");
+    assert_eq!(add.string.snippet(), "add(${1:int a}, ${2:int b})");
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn test_completion_render_optional() {
+    use clang_sys::completion::*;
+
+    let string = |chunks| CompletionString {
+        chunks,
+        priority: 0,
+        availability: CXAvailability_Available,
+        annotations: vec![],
+        brief_comment: None,
+    };
+    let text = |kind, text: &str| CompletionChunk::Text {
+        kind,
+        text: text.into(),
+    };
+
+    let optional = string(vec![
+        text(CXCompletionChunk_Comma, ", "),
+        text(CXCompletionChunk_Placeholder, "int b"),
+    ]);
+    let string = string(vec![
+        text(CXCompletionChunk_ResultType, "int"),
+        text(CXCompletionChunk_TypedText, "f$"),
+        text(CXCompletionChunk_LeftParen, "("),
+        text(CXCompletionChunk_Placeholder, "map<a, {}>"),
+        CompletionChunk::Optional(optional),
+        text(CXCompletionChunk_RightParen, ")"),
+        text(CXCompletionChunk_Informative, " const"),
+    ]);
+
+    assert_eq!(string.label(), "f$(map<a, {}>, int b) const");
+    assert_eq!(string.filter_text(), "f$");
+    assert_eq!(string.snippet(), "f\\$(${1:map<a, {\\}>})");
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn test_diagnostic() {
+    use clang_sys::index::*;
+
+    init();
+
+    let source = "void f(void) { int unused; }\nint g(void) { return 0 }\n";
+    let unsaved = &[Unsaved::new("diagnostic.c", source)];
+    let index = Index::new(false, false);
+    let tu =
+        TranslationUnit::parse(&index, "diagnostic.c", &["-Wunused-variable"], unsaved, 0).unwrap();
+
+    let diagnostics = tu.diagnostics();
+    assert_eq!(diagnostics.len(), 2);
+
+    let warning = &diagnostics[0];
+    assert_eq!(warning.severity, CXDiagnostic_Warning);
+    assert_eq!(warning.location.file.as_deref(), Some("diagnostic.c"));
+    assert_eq!((warning.location.line, warning.location.column), (1, 20));
+    assert_eq!(warning.option.as_deref(), Some("-Wunused-variable"));
+    assert_eq!(
+        warning.disable_option.as_deref(),
+        Some("-Wno-unused-variable")
+    );
+    assert!(!warning.is_error());
+
+    let error = &diagnostics[1];
+    assert!(error.is_error());
+    assert_eq!(error.location.line, 2);
+    assert_eq!(error.fix_its.len(), 1);
+    assert_eq!(error.fix_its[0].replacement, ";");
+    assert_eq!(error.iter().count(), 1 + error.children.len());
+    assert!(error.to_string().starts_with("diagnostic.c:2:"));
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn test_diagnostic_load() {
+    use std::fs;
+    use std::process::Command;
+
+    use clang_sys::diagnostic::{self, LoadDiagnosticsError};
+    use clang_sys::support::Clang;
+
+    init();
+
+    let tmp = tempfile::Builder::new()
+        .prefix("clang_sys_test")
+        .tempdir()
+        .unwrap();
+
+    let error = diagnostic::load(tmp.path().join("missing.dia")).unwrap_err();
+    assert!(matches!(error, LoadDiagnosticsError::CannotLoad(_)));
+    assert_eq!(error.to_raw(), CXLoadDiag_CannotLoad);
+
+    let invalid = tmp.path().join("invalid.dia");
+    fs::write(&invalid, "not a serialized diagnostics file").unwrap();
+    let error = diagnostic::load(&invalid).unwrap_err();
+    assert!(matches!(error, LoadDiagnosticsError::InvalidFile(_)));
+
+    // The serialized diagnostics file is generated with a local `clang`.
+    let clang = match Clang::find(None, &[]) {
+        Some(clang) => clang,
+        None => return,
+    };
+
+    let source = tmp.path().join("source.c");
+    fs::write(&source, "void f(void) { int unused; }\n").unwrap();
+    let dia = tmp.path().join("source.dia");
+    let status = Command::new(&clang.path)
+        .args([
+            "-fsyntax-only",
+            "-Wunused-variable",
+            "--serialize-diagnostics",
+        ])
+        .arg(&dia)
+        .arg(&source)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let diagnostics = diagnostic::load(&dia).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, CXDiagnostic_Warning);
+    assert_eq!(diagnostics[0].option.as_deref(), Some("-Wunused-variable"));
+    assert_eq!(
+        (diagnostics[0].location.line, diagnostics[0].location.column),
+        (1, 20)
+    );
+}
+
+#[cfg(feature = "report")]
+#[test]
+fn test_report() {
+    use clang_sys::diagnostic::*;
+    use clang_sys::report;
+    use clang_sys::source::*;
+
+    let location = |line, column| SourceLocation {
+        file: Some("a.c".into()),
+        line,
+        column,
+        offset: 0,
+    };
+
+    let note = Diagnostic {
+        severity: CXDiagnostic_Note,
+        location: SourceLocation {
+            file: Some(r"C:\src\my file.c".into()),
+            line: 1,
+            column: 5,
+            offset: 0,
+        },
+        spelling: "previous definition is here".into(),
+        category: "".into(),
+        option: None,
+        disable_option: None,
+        ranges: vec![],
+        fix_its: vec![],
+        children: vec![],
+    };
+
+    let error = Diagnostic {
+        severity: CXDiagnostic_Error,
+        location: location(2, 24),
+        spelling: "expected \";\"".into(),
+        category: "Parse Issue".into(),
+        option: None,
+        disable_option: None,
+        ranges: vec![],
+        fix_its: vec![FixIt {
+            range: SourceRange {
+                start: location(2, 24),
+                end: location(2, 24),
+            },
+            replacement: ";".into(),
+        }],
+        children: vec![note],
+    };
+
+    let json = report::to_json(std::slice::from_ref(&error));
+    assert!(json.starts_with(
+        r#"[{"severity":"error","location":{"file":"a.c","line":2,"column":24,"offset":0}"#
+    ));
+    assert!(json.contains(r#""message":"expected \";\"""#));
+    assert!(json.contains(r#""option":null"#));
+    assert!(json.contains(r#""children":[{"severity":"note""#));
+
+    let sarif = report::to_sarif(&[error]);
+    assert!(sarif.contains(r#""version":"2.1.0""#));
+    assert!(sarif.contains(r#""results":[{"level":"error","message":{"text":"expected \";\""}"#));
+    assert!(sarif.contains(r#""region":{"startLine":2,"startColumn":24}"#));
+    assert!(sarif.contains(r#""artifactLocation":{"uri":"a.c","uriBaseId":"%SRCROOT%"}"#));
+    assert!(sarif.contains(r#""relatedLocations":[{"id":0,"#));
+    assert!(sarif.contains(r#""artifactLocation":{"uri":"file:///C:/src/my%20file.c"}"#));
+    assert!(sarif.contains(r#""insertedContent":{"text":";"}"#));
+    assert!(sarif.contains(r#""properties":{"category":"Parse Issue"}"#));
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn test_token() {
+    use clang_sys::index::*;
+    use clang_sys::token::*;
+
+    init();
+
+    let index = Index::new(false, false);
+    let tu = TranslationUnit::parse(&index, "tests/header.h", &[], &[], 0).unwrap();
+    let range = unsafe { clang_getCursorExtent(tu.cursor()) };
+
+    let mut tokens = unsafe { Tokens::tokenize(&tu, range) };
+    let spellings = tokens.iter().map(|t| t.spelling).collect::<Vec<_>>();
+    assert!(spellings.windows(3).any(|w| w == ["int", "add", "("]));
+    assert!(tokens.iter().all(|t| t.cursor.is_none()));
+
+    let add = tokens.iter().find(|t| t.spelling == "add").unwrap();
+    assert_eq!(add.kind, CXToken_Identifier);
+    assert_eq!((add.extent.start.line, add.extent.start.column), (4, 5));
+
+    tokens.annotate();
+    let add = tokens.iter().find(|t| t.spelling == "add").unwrap();
+    assert_eq!(
+        unsafe { clang_getCursorKind(add.cursor.unwrap()) },
+        CXCursor_FunctionDecl
+    );
+
+    let add = tokens
+        .as_raw()
+        .iter()
+        .find(|t| unsafe { clang_getTokenKind(**t) } == CXToken_Identifier);
+    unsafe { tokens.retokenize(clang_getTokenExtent(tu.as_raw(), *add.unwrap())) };
+    assert_eq!(tokens.len(), 1);
+    assert!(tokens.get(0).unwrap().cursor.is_none());
+    assert!(tokens.get(1).is_none());
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn test_visit() {
+    use clang_sys::index::*;
+    use clang_sys::string::ClangString;
+    use clang_sys::visit::*;
+
+    init();
+
+    let index = Index::new(false, false);
+    let tu = TranslationUnit::parse(&index, "tests/header.h", &[], &[], 0).unwrap();
+
+    let children = unsafe { children(tu.cursor()) };
+    let add = children.iter().find(|c| unsafe {
+        let spelling = ClangString::from_raw(clang_getCursorSpelling(**c));
+        clang_getCursorKind(**c) == CXCursor_FunctionDecl && spelling.to_str() == Ok("add")
+    });
+    let add = *add.unwrap();
+
+    let mut parameters = 0;
+    let terminated = unsafe {
+        visit_children(add, |c, p| {
+            assert_eq!(clang_equalCursors(p, add), 1);
+            if clang_getCursorKind(c) == CXCursor_ParmDecl {
+                parameters += 1;
+            }
+            CXChildVisit_Continue
+        })
+    };
+    assert!(!terminated);
+    assert_eq!(parameters, 2);
+
+    assert!(unsafe { visit_children(add, |_, _| CXChildVisit_Break) });
+
+    let result = std::panic::catch_unwind(|| unsafe {
+        visit_children(add, |_, _| panic!("visitor panicked"))
+    });
+    let payload = result.unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"visitor panicked"));
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn test_visit_walk() {
+    use clang_sys::index::*;
+    use clang_sys::visit::*;
+
+    init();
+
+    let index = Index::new(false, false);
+    let tu = TranslationUnit::parse(&index, "tests/header.h", &[], &[], 0).unwrap();
+
+    let all = unsafe { walk(tu.cursor()) }.collect::<Vec<_>>();
+    assert!(all
+        .iter()
+        .any(|(d, _, c)| *d == 1 && unsafe { clang_getCursorKind(*c) } == CXCursor_FunctionDecl));
+    assert!(all
+        .iter()
+        .any(|(d, _, c)| *d == 2 && unsafe { clang_getCursorKind(*c) } == CXCursor_ParmDecl));
+
+    let mut pruned = vec![];
+    let mut walk = unsafe { walk(tu.cursor()) };
+    while let Some((depth, parent, cursor)) = walk.next() {
+        assert_eq!(unsafe { clang_equalCursors(parent, tu.cursor()) }, 1);
+        pruned.push((depth, cursor));
+        walk.skip_children();
+    }
+    assert_eq!(pruned.len(), unsafe { children(tu.cursor()) }.len());
+    assert!(pruned.iter().all(|(d, _)| *d == 1));
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn test_xref() {
+    use clang_sys::index::*;
+    use clang_sys::indexer::IndexAction;
+    use clang_sys::xref::*;
+
+    init();
+
+    let first = "#include \"header.h\"\nint twice(int a) { return add(a, a); }\n";
+    let second = "#include \"header.h\"\nint add(int a, int b) { return a + b; }\n";
+    let unsaved = &[
+        Unsaved::new("first.c", first),
+        Unsaved::new("second.c", second),
+    ];
+
+    let index = Index::new(false, false);
+    let action = IndexAction::new(&index);
+    let mut database = Database::new();
+    database
+        .index_source_file(&action, "first.c", &["-Itests"], unsaved, 0)
+        .unwrap();
+    database
+        .index_source_file(&action, "second.c", &["-Itests"], unsaved, 0)
+        .unwrap();
+
+    let add = database.get("c:@F@add").unwrap();
+    assert_eq!(add.name, "add");
+    assert_eq!(add.kind, CXIdxEntity_Function);
+
+    let declarations = add.declarations().collect::<Vec<_>>();
+    assert_eq!(declarations.len(), 1);
+    assert!(declarations[0]
+        .location
+        .file
+        .as_ref()
+        .unwrap()
+        .ends_with("header.h"));
+    assert_eq!(
+        (
+            declarations[0].location.line,
+            declarations[0].location.column
+        ),
+        (4, 5)
+    );
+
+    let definitions = add.definitions().map(|o| &o.location).collect::<Vec<_>>();
+    assert_eq!(definitions.len(), 1);
+    assert_eq!(
+        (definitions[0].file.as_deref(), definitions[0].line),
+        (Some("second.c"), 2)
+    );
+
+    let references = add.references().map(|o| &o.location).collect::<Vec<_>>();
+    assert_eq!(references.len(), 1);
+    assert_eq!(
+        (references[0].file.as_deref(), references[0].line),
+        (Some("first.c"), 2)
+    );
+
+    let mut bytes = vec![];
+    database.write(&mut bytes).unwrap();
+    let read = Database::read(&bytes[..]).unwrap();
+    assert_eq!(
+        read.iter().collect::<Vec<_>>(),
+        database.iter().collect::<Vec<_>>()
+    );
+}
+
+#[cfg(feature = "safe")]
+#[test]
+fn test_xref_serialization() {
+    use clang_sys::source::SourceLocation;
+    use clang_sys::xref::*;
+
+    let location = |file: Option<&str>, line| SourceLocation {
+        file: file.map(|f| f.into()),
+        line,
+        column: 1,
+        offset: 0,
+    };
+
+    let mut database = Database::new();
+    let occurrence = Occurrence {
+        kind: OccurrenceKind::Definition,
+        location: location(Some("a\tb.c"), 1),
+        role: 0,
+    };
+    assert_eq!(
+        database.insert("c:@F@f", "f", CXIdxEntity_Function, occurrence.clone()),
+        0
+    );
+    assert_eq!(
+        database.insert("c:@F@f", "f", CXIdxEntity_Function, occurrence),
+        0
+    );
+    let occurrence = Occurrence {
+        kind: OccurrenceKind::Reference,
+        location: location(None, 2),
+        role: 32,
+    };
+    assert_eq!(
+        database.insert("c:@F@g\\", "g\nh", CXIdxEntity_Function, occurrence),
+        1
+    );
+    assert_eq!(database.len(), 2);
+    assert_eq!(database.get("c:@F@f").unwrap().occurrences.len(), 1);
+
+    let mut bytes = vec![];
+    database.write(&mut bytes).unwrap();
+    assert_eq!(String::from_utf8(bytes.clone()).unwrap().lines().count(), 5);
+
+    let read = Database::read(&bytes[..]).unwrap();
+    assert_eq!(
+        read.iter().collect::<Vec<_>>(),
+        database.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(read.get("c:@F@g\\").unwrap().name, "g\nh");
+
+    let error = Database::read(&b"clang-sys xref 1\nreference\t0\t1\t1\t0\t\n"[..]).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert!(Database::read(&b"unknown\n"[..]).is_err());
+}
+
+#[test]
+fn test_enums() {
+    use std::convert::TryFrom;
+
+    use clang_sys::enums::{self, UnknownValue};
+
+    let kind = enums::CXCursorKind::try_from(CXCursor_FunctionDecl).unwrap();
+    assert_eq!(kind, enums::CXCursorKind::CXCursor_FunctionDecl);
+    assert_eq!(CXCursorKind::from(kind), CXCursor_FunctionDecl);
+    assert_eq!(kind.to_string(), "CXCursor_FunctionDecl");
+
+    let error = enums::CXCursorKind::try_from(-1).unwrap_err();
+    assert_eq!(
+        error,
+        UnknownValue {
+            name: "CXCursorKind",
+            value: -1
+        }
+    );
+
+    assert_eq!(
+        enums::CXTypeLayoutError::try_from(-6),
+        Ok(enums::CXTypeLayoutError::CXTypeLayoutError_Undeduced)
+    );
+
+    for severity in enums::CXDiagnosticSeverity::ALL {
+        let value = CXDiagnosticSeverity::from(*severity);
+        assert_eq!(enums::CXDiagnosticSeverity::try_from(value), Ok(*severity));
+    }
+    assert_eq!(enums::CXDiagnosticSeverity::ALL.len(), 5);
+
+    // `CXCursor_TranslationUnit` has the same value as
+    // `CXCursor_OMPParallelMaskedDirective` before `libclang` 15.0.
+    assert_eq!(
+        enums::CXCursorKind::try_from(CXCursor_TranslationUnit),
+        Ok(enums::CXCursorKind::CXCursor_TranslationUnit)
+    );
+    let masked = enums::CXCursorKind::CXCursor_OMPParallelMaskedDirective;
+    assert_eq!(
+        enums::CXCursorKind::ALL.contains(&masked),
+        cfg!(feature = "clang_15_0")
+    );
+
+    let mut values = enums::CXCursorKind::ALL
+        .iter()
+        .map(|k| CXCursorKind::from(*k))
+        .collect::<Vec<_>>();
+    values.sort();
+    values.dedup();
+    assert_eq!(values.len(), enums::CXCursorKind::ALL.len());
+
+    for kind in enums::CXCursorKind::ALL {
+        let value = CXCursorKind::from(*kind);
+        assert_eq!(enums::CXCursorKind::try_from(value), Ok(*kind));
+    }
+}
+
+#[test]
+fn test_enums_names() {
+    use clang_sys::enums::{self, UnknownName};
+
+    assert_eq!(
+        enums::CXCursorKind::name_of(CXCursor_FunctionDecl),
+        Some("CXCursor_FunctionDecl")
+    );
+    assert_eq!(enums::CXCursorKind::name_of(-1), None);
+    assert_eq!(
+        enums::CXCursorKind::value_of("CXCursor_FunctionDecl"),
+        Some(CXCursor_FunctionDecl)
+    );
+    assert_eq!(
+        enums::CXCursorKind::value_of("CXCursor_TranslationUnit"),
+        Some(CXCursor_TranslationUnit)
+    );
+    assert_eq!(enums::CXCursorKind::value_of("CXCursor_Unknown"), None);
+    assert_eq!(
+        enums::CXCursorKind::name_of(CXCursor_TranslationUnit),
+        Some("CXCursor_TranslationUnit")
+    );
+
+    for kind in enums::CXCursorKind::ALL {
+        let value = enums::CXCursorKind::value_of(kind.name());
+        assert_eq!(
+            value.and_then(enums::CXCursorKind::name_of),
+            Some(kind.name())
+        );
+    }
+
+    let error = "CXCursor_Unknown"
+        .parse::<enums::CXCursorKind>()
+        .unwrap_err();
+    assert_eq!(
+        error,
+        UnknownName {
+            name: "CXCursorKind",
+            value: "CXCursor_Unknown".into()
+        }
+    );
+
+    let keep_going = enums::CXTranslationUnit_Flags::value_of("CXTranslationUnit_KeepGoing");
+    if cfg!(feature = "clang_3_9") {
+        assert_eq!(keep_going, Some(512));
+    } else {
+        assert_eq!(keep_going, None);
+    }
+}
+
+#[test]
+fn test_flags() {
+    use clang_sys::flags::{CXGlobalOptFlags, CXTranslationUnit_Flags};
+
+    let mut flags = CXTranslationUnit_Flags::CXTranslationUnit_DetailedPreprocessingRecord;
+    flags.insert(CXTranslationUnit_Flags::CXTranslationUnit_SkipFunctionBodies);
+    assert_eq!(
+        flags.bits(),
+        CXTranslationUnit_DetailedPreprocessingRecord | CXTranslationUnit_SkipFunctionBodies
+    );
+    assert!(flags.contains(CXTranslationUnit_Flags::CXTranslationUnit_SkipFunctionBodies));
+    assert!(!flags.contains(CXTranslationUnit_Flags::CXTranslationUnit_Incomplete));
+    assert_eq!(
+        format!("{:?}", flags),
+        "DetailedPreprocessingRecord | SkipFunctionBodies"
+    );
+
+    let iter = flags.iter().collect::<Vec<_>>();
+    assert_eq!(
+        iter,
+        &[
+            CXTranslationUnit_Flags::CXTranslationUnit_DetailedPreprocessingRecord,
+            CXTranslationUnit_Flags::CXTranslationUnit_SkipFunctionBodies,
+        ]
+    );
+
+    flags.remove(CXTranslationUnit_Flags::CXTranslationUnit_DetailedPreprocessingRecord);
+    assert_eq!(
+        flags,
+        CXTranslationUnit_Flags::CXTranslationUnit_SkipFunctionBodies
+    );
+
+    assert_eq!(format!("{:?}", CXTranslationUnit_Flags::empty()), "(empty)");
+    assert_eq!(
+        format!("{:?}", CXTranslationUnit_Flags::from(1 << 30)),
+        "0x40000000"
+    );
+    assert_eq!(
+        format!("{:?}", CXTranslationUnit_Flags::from(2 | 1 << 30)),
+        "Incomplete | 0x40000000"
+    );
+
+    let all = CXGlobalOptFlags::CXGlobalOpt_ThreadBackgroundPriorityForAll;
+    assert_eq!(
+        format!("{:?}", all),
+        "ThreadBackgroundPriorityForIndexing | ThreadBackgroundPriorityForEditing"
+    );
+
+    #[cfg(feature = "clang_17_0")]
+    {
+        use clang_sys::flags::CXIndexOptions_Flags;
+
+        let flags = CXIndexOptions_Flags::CXIndexOptions_DisplayDiagnostics
+            | CXIndexOptions_Flags::CXIndexOptions_StorePreamblesInMemory;
+        let bits: clang_sys::CXIndexOptions_Flags = flags.bits();
+        assert_eq!(
+            bits,
+            CXIndexOptions_DisplayDiagnostics | CXIndexOptions_StorePreamblesInMemory
+        );
+        assert_eq!(
+            format!("{:?}", flags),
+            "DisplayDiagnostics | StorePreamblesInMemory"
+        );
+    }
+}
+
+#[test]
+fn test_support_parse_clang_version() {
+    let versions = &[
+        ("clang version 3.5.0 (tags/RELEASE_350/final)", Some((3, 5, 0))),
+        ("clang version 14.0.6", Some((14, 0, 6))),
+        ("clang version 17.0.0 (https://github.com/llvm/llvm-project.git 6009708b4367)", Some((17, 0, 0))),
+        ("clang version 19.0.0git (https://github.com/llvm/llvm-project 4a7a7b1b8d4e)", Some((19, 0, 0))),
+        ("clang version 20.1.0-rc2", Some((20, 1, 0))),
+        ("clang version 18", Some((18, 0, 0))),
+        ("clang version 11.1.0 (Red Hat 11.1.0-1.module+el8.4.0+12483+89b287b0)", Some((11, 1, 0))),
+        ("Ubuntu clang version 14.0.0-1ubuntu1.1", Some((14, 0, 0))),
+        ("Ubuntu clang version 18.1.3 (1ubuntu1)", Some((18, 1, 3))),
+        ("Debian clang version 16.0.6 (15~deb12u1)", Some((16, 0, 6))),
+        ("Fedora clang version 17.0.6 (Fedora 17.0.6-2.fc39)", Some((17, 0, 6))),
+        ("Homebrew clang version 18.1.8", Some((18, 1, 8))),
+        ("FreeBSD clang version 16.0.6 (https://github.com/llvm/llvm-project.git llvmorg-16.0.6-0-g7cbf1a259152)", Some((16, 0, 6))),
+        ("Apple clang version 15.0.0 (clang-1500.3.9.4)", Some((15, 0, 0))),
+        ("Apple LLVM version 10.0.0 (clang-1000.11.45.5)", Some((10, 0, 0))),
+        ("Apple LLVM version 8.0.0 (clang-800.0.42.1)", Some((8, 0, 0))),
+        ("Android (8490178, based on r450784d) clang version 14.0.6 (https://android.googlesource.com/toolchain/llvm-project 4c603efb0cca074e9238af8b4106c30add4418f6)", Some((14, 0, 6))),
+        ("AMD clang version 16.0.0 (https://github.com/RadeonOpenCompute/llvm-project roc-5.7.0 23352 d1e13c532a947d0cbfc94759c00dcf152294aa13)", Some((16, 0, 0))),
+        ("Custom build version control: clang version 13.0.1", Some((13, 0, 1))),
+        ("clang version 12.0.1\nTarget: x86_64-pc-linux-gnu\nThread model: posix", Some((12, 0, 1))),
+        ("Intel(R) oneAPI DPC++/C++ Compiler 2023.2.0 (2023.2.0.20230721)", None),
+        ("clang version unknown", None),
+        ("", None),
+    ];
+
+    for (string, expected) in versions {
+        let version = support::parse_clang_version(string).map(|v| (v.Major, v.Minor, v.Subminor));
+        assert_eq!(version, *expected, "{:?}", string);
+    }
+}
+
+#[cfg(feature = "runtime")]
+#[test]
+fn test_version() {
+    assert_eq!(Version::V17_0.to_string(), "17.0.x");
+    assert_eq!(Version::V18_0.to_string(), "18.0.x");
+    assert_eq!(Version::V19_0.to_string(), "19.0.x");
+    assert_eq!(Version::V20_0.to_string(), "20.0.x or later");
+    assert!(Version::V17_0 < Version::V18_0 && Version::V19_0 < Version::V20_0);
+
+    let library = load_manually().unwrap();
+    let version = library.version().unwrap();
+    println!("{}", version);
+
+    #[cfg(feature = "clang_19_0")]
+    if library.functions.clang_Cursor_getBinaryOpcode.is_some() {
+        assert!(version >= Version::V19_0);
+    }
+}
+
+#[cfg(feature = "runtime")]
+#[test]
+fn test_clang_version() {
+    let library = load_manually().unwrap();
+    let version = library.clang_version().unwrap();
+    println!("{}.{}.{}", version.Major, version.Minor, version.Subminor);
+    assert!(version.Major >= 3);
+}
+
 #[test]
 fn test_support() {
     let clang = support::Clang::find(None, &[]).unwrap();